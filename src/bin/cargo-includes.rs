@@ -0,0 +1,95 @@
+//! Regenerates the READMEs of all members of a cargo workspace, run as
+//! `cargo includes`, or with `cargo includes --check` on CI.
+use std::{path::PathBuf, process::ExitCode};
+
+use anyhow::{bail, Result};
+use clap::{Args, Parser};
+use markdown_includes::{FileConfig, Mode, Processor, ProjectConfig};
+
+#[derive(Parser)]
+#[command(name = "cargo", bin_name = "cargo")]
+enum Cargo {
+    Includes(Includes),
+}
+
+/// Regenerate the README of every workspace member from its template
+#[derive(Args)]
+#[command(version)]
+struct Includes {
+    /// Fail with the differences if any README is out of sync, instead of updating it
+    #[arg(long)]
+    check: bool,
+    /// Path to the Cargo.toml of the workspace
+    #[arg(long, value_name = "PATH")]
+    manifest_path: Option<PathBuf>,
+    /// Only process these workspace members
+    #[arg(short, long, value_name = "SPEC")]
+    package: Vec<String>,
+    /// Fail on unknown fence names and configuration keys
+    #[arg(long)]
+    strict: bool,
+    /// Fail on warnings, such as intralinks that could not be resolved
+    #[arg(long)]
+    deny_warnings: bool,
+}
+
+fn main() -> ExitCode {
+    let Cargo::Includes(includes) = Cargo::parse();
+    match run(includes) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Runs the command, returning whether it succeeded
+fn run(includes: Includes) -> Result<bool> {
+    let manifest = includes.manifest_path.as_deref();
+    let mut project = ProjectConfig::from_workspace(manifest.unwrap_or(".".as_ref()))?;
+    if !includes.package.is_empty() {
+        let member = |file: &FileConfig| {
+            file.defaults
+                .get("rustdoc")
+                .and_then(|rustdoc| rustdoc.get("workspace_project"))
+                .and_then(|member| member.as_str())
+                .map(str::to_string)
+        };
+        if let Some(missing) = includes
+            .package
+            .iter()
+            .find(|p| !project.files.iter().any(|f| member(f).as_ref() == Some(p)))
+        {
+            bail!("`{missing}` is not a workspace member with a README template");
+        }
+        project
+            .files
+            .retain(|file| member(file).is_some_and(|m| includes.package.contains(&m)));
+    }
+
+    let processor = Processor::new()
+        .project_root(&project.root)
+        .strict(includes.strict)
+        .deny_warnings(includes.deny_warnings)
+        .diagnostics(|diagnostic| eprintln!("{diagnostic}"));
+
+    if includes.check {
+        let report = processor.check_project(&project);
+        print!("{report}");
+        return Ok(report.is_up_to_date());
+    }
+
+    let mut success = true;
+    for file in processor.mode(Mode::Update).process_project(&project) {
+        match file.result {
+            Ok(()) => println!("processed {}", file.destination.display()),
+            Err(e) => {
+                eprintln!("{}: {e:#}", file.template.display());
+                success = false;
+            }
+        }
+    }
+    Ok(success)
+}
@@ -0,0 +1,187 @@
+//! Processes markdown templates from the command line.
+//!
+//! ```text
+//! markdown-includes update src/README.tpl.md README.md
+//! markdown-includes check --config markdown-includes.toml
+//! markdown-includes render src/README.tpl.md
+//! markdown-includes render --config markdown-includes.toml
+//! markdown-includes watch src/README.tpl.md README.md
+//! ```
+use std::{path::PathBuf, process::ExitCode, time::Duration};
+
+use anyhow::{bail, Result};
+use clap::{Args, Parser, Subcommand};
+use markdown_includes::{Mode, Processor, ProjectConfig, Watcher};
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// Fail on unknown fence names and configuration keys
+    #[arg(long, global = true)]
+    strict: bool,
+    /// Fail on warnings, such as intralinks that could not be resolved
+    #[arg(long, global = true)]
+    deny_warnings: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Regenerate the destination files that are out of sync with their template
+    Update(Files),
+    /// Fail with the differences if any destination file is out of sync
+    Check(Files),
+    /// Regenerate the destination files whenever a file they depend on changes
+    Watch {
+        #[command(flatten)]
+        files: Files,
+        /// How often to look for changes, in milliseconds
+        #[arg(long, default_value_t = 500)]
+        interval: u64,
+    },
+    /// Print the documents generated from the templates to stdout
+    Render {
+        /// The templates to render
+        templates: Vec<PathBuf>,
+        /// The project configuration whose files are rendered when no templates
+        /// are given, see `update --config`
+        #[arg(long, conflicts_with = "templates")]
+        config: Option<PathBuf>,
+    },
+}
+
+/// The files to process: template and destination pairs, or else
+/// the files listed in a project configuration.
+#[derive(Args)]
+struct Files {
+    /// Template and destination pairs, such as `src/README.tpl.md README.md`
+    #[arg(value_name = "TEMPLATE DESTINATION")]
+    pairs: Vec<PathBuf>,
+    /// The project configuration, either a markdown-includes.toml file, a
+    /// Cargo.toml with markdown-includes metadata or a directory holding one
+    /// of them. Used when no pairs are given, defaults to the current directory.
+    #[arg(long, conflicts_with = "pairs")]
+    config: Option<PathBuf>,
+}
+
+enum Target {
+    Pairs(Vec<(PathBuf, PathBuf)>),
+    Project(ProjectConfig),
+}
+
+impl Files {
+    fn target(self) -> Result<Target> {
+        if !self.pairs.is_empty() {
+            let chunks = self.pairs.chunks_exact(2);
+            if let [template] = chunks.remainder() {
+                bail!(
+                    "expected template and destination pairs, but {template:?} has no destination"
+                );
+            }
+            let pairs = chunks
+                .map(|pair| (pair[0].clone(), pair[1].clone()))
+                .collect();
+            return Ok(Target::Pairs(pairs));
+        }
+
+        Ok(Target::Project(load_project(self.config)?))
+    }
+}
+
+/// The project configuration in or at `config`, the current directory by default
+fn load_project(config: Option<PathBuf>) -> Result<ProjectConfig> {
+    let config = config.unwrap_or_else(|| PathBuf::from("."));
+    match config.is_dir() {
+        true => ProjectConfig::load(&config),
+        false => ProjectConfig::from_file(&config),
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Runs the command, returning whether it succeeded
+fn run(cli: Cli) -> Result<bool> {
+    // stdout is reserved for rendered documents
+    let processor = Processor::new()
+        .strict(cli.strict)
+        .deny_warnings(cli.deny_warnings)
+        .diagnostics(|diagnostic| eprintln!("{diagnostic}"));
+
+    match cli.command {
+        Command::Update(files) => {
+            let processor = processor.mode(Mode::Update);
+            let results = match files.target()? {
+                Target::Pairs(pairs) => pairs
+                    .into_iter()
+                    .map(|(template, destination)| {
+                        let result = processor.process(&template, &destination);
+                        (template, result)
+                    })
+                    .collect::<Vec<_>>(),
+                Target::Project(project) => processor
+                    .process_project(&project)
+                    .into_iter()
+                    .map(|file| (file.template, file.result))
+                    .collect(),
+            };
+
+            let mut success = true;
+            for (template, result) in results {
+                if let Err(e) = result {
+                    eprintln!("{}: {e:#}", template.display());
+                    success = false;
+                }
+            }
+            Ok(success)
+        }
+        Command::Check(files) => {
+            let report = match files.target()? {
+                Target::Pairs(pairs) => processor.check_all(pairs),
+                Target::Project(project) => processor.check_project(&project),
+            };
+            print!("{report}");
+            Ok(report.is_up_to_date())
+        }
+        Command::Watch { files, interval } => {
+            let processor = processor.mode(Mode::Update);
+            let mut watcher = match files.target()? {
+                Target::Pairs(pairs) => Watcher::new(processor, pairs),
+                Target::Project(project) => Watcher::project(processor, &project),
+            };
+            watcher.run(Duration::from_millis(interval), |file| match &file.result {
+                Ok(()) => println!("processed {}", file.template.display()),
+                Err(e) => eprintln!("{}: {e:#}", file.template.display()),
+            })
+        }
+        Command::Render { templates, config } => {
+            if !templates.is_empty() {
+                for template in templates {
+                    print!("{}", processor.render(&template)?);
+                }
+                return Ok(true);
+            }
+
+            let mut success = true;
+            for (template, document) in processor.render_project(&load_project(config)?) {
+                match document {
+                    Ok(document) => print!("{document}"),
+                    Err(e) => {
+                        eprintln!("{}: {e:#}", template.display());
+                        success = false;
+                    }
+                }
+            }
+            Ok(success)
+        }
+    }
+}
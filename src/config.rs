@@ -0,0 +1,254 @@
+use fs_err as fs;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use toml::{Table, Value};
+
+use crate::{BannerPosition, Syntax};
+
+/// The templates of a project and the defaults shared between them, read from
+/// a _markdown-includes.toml_ file or from the `[workspace.metadata.markdown-includes]`
+/// or `[package.metadata.markdown-includes]` table of a _Cargo.toml_.
+///
+/// ```toml
+/// [defaults.toc]
+/// header = "## Contents"
+///
+/// [defaults.rustdoc.intralinks]
+/// strip_links = true
+///
+/// [[files]]
+/// template = "src/README.tpl.md"
+/// destination = "README.md"
+/// syntax = "comments"
+///
+/// [[files]]
+/// template = "crates/core/src/README.tpl.md"
+/// destination = "crates/core/README.md"
+/// defaults.rustdoc.workspace_project = "core"
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// The directory the configuration was read from, which
+    /// the template and destination paths are relative to
+    #[serde(skip)]
+    pub root: PathBuf,
+    /// Default fence configurations, keyed by fence name
+    #[serde(default)]
+    pub defaults: Table,
+    /// The banner of all the generated documents
+    #[serde(default)]
+    pub banner: BannerConfig,
+    /// How the fences of all the templates are written
+    pub syntax: Option<Syntax>,
+    #[serde(default)]
+    pub files: Vec<FileConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    pub template: PathBuf,
+    pub destination: PathBuf,
+    /// Default fence configurations for this file only,
+    /// taking precedence over the project's
+    #[serde(default)]
+    pub defaults: Table,
+    /// The banner of this file only, taking precedence over the project's
+    #[serde(default)]
+    pub banner: BannerConfig,
+    /// How the fences of this template are written, taking precedence over the project's
+    pub syntax: Option<Syntax>,
+}
+
+/// The banner settings of a project or a file. Settings that are
+/// not given are taken from the project or the [`crate::Processor`].
+///
+/// ```toml
+/// [banner]
+/// text = "<!-- Generated from {template} by `{command}`, please don't edit -->"
+/// position = "after-front-matter"
+/// command = "cargo test update_readme"
+///
+/// [[files]]
+/// template = "src/README.tpl.md"
+/// destination = "README.md"
+/// banner.enabled = false
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BannerConfig {
+    /// `false` to leave out the banner
+    pub enabled: Option<bool>,
+    /// A custom banner text, see [`crate::Banner::Custom`]
+    pub text: Option<String>,
+    pub position: Option<BannerPosition>,
+    /// Replaces `{command}` in the banner text
+    pub command: Option<String>,
+}
+
+/// The outcome of processing one of the files of a [`ProjectConfig`].
+#[derive(Debug)]
+pub struct FileResult {
+    pub template: PathBuf,
+    pub destination: PathBuf,
+    pub result: Result<()>,
+}
+
+impl ProjectConfig {
+    pub const FILE_NAME: &'static str = "markdown-includes.toml";
+
+    /// Reads the project configuration in `dir`, either from a _markdown-includes.toml_
+    /// file, or if there is none, from the metadata of the _Cargo.toml_.
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref();
+        let config_file = dir.join(Self::FILE_NAME);
+        let manifest = dir.join("Cargo.toml");
+
+        if config_file.is_file() {
+            Self::from_file(config_file)
+        } else if manifest.is_file() {
+            Self::from_file(manifest)
+        } else {
+            bail!(
+                "no {} or markdown-includes metadata in Cargo.toml found in {dir:?}",
+                Self::FILE_NAME
+            );
+        }
+    }
+
+    /// Reads the project configuration from a configuration file, or from
+    /// the metadata if `file` is a _Cargo.toml_. The paths in the configuration
+    /// are relative to the directory of the file.
+    pub fn from_file<P: AsRef<Path>>(file: P) -> Result<Self> {
+        let file = file.as_ref();
+        let mut config = if file.file_name() == Some("Cargo.toml".as_ref()) {
+            Self::from_manifest(file)?
+                .context(format!("no markdown-includes metadata found in {file:?}"))?
+        } else {
+            Self::from_str(&fs::read_to_string(file)?)
+                .context(format!("failed to parse {file:?}"))?
+        };
+        config.root = file.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(config)
+    }
+
+    /// A project with the _README_ of every member of the cargo workspace in `path`,
+    /// a directory or a _Cargo.toml_, as given by its `package.readme`. The template of a _README.md_ is either
+    /// _src/README.tpl.md_ or _README.tpl.md_ in the member's directory, or the
+    /// _README.md_ itself when it has in-place markers. Members without a readme or a
+    /// template are left out.
+    ///
+    /// The rustdoc fences of each member use the member as `workspace_project`, and
+    /// the paths are relative to the workspace root.
+    pub fn from_workspace<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut command = cargo_metadata::MetadataCommand::new();
+        match path.is_file() {
+            true => command.manifest_path(path),
+            false => command.current_dir(path),
+        };
+        let metadata = command
+            .no_deps()
+            .exec()
+            .context("failed to read the cargo metadata")?;
+        let root = metadata.workspace_root.as_std_path();
+
+        let mut files = Vec::new();
+        for package in metadata.workspace_packages() {
+            let Some(package_dir) = package.manifest_path.parent() else {
+                continue;
+            };
+            let Some(readme) = &package.readme else {
+                continue;
+            };
+            let destination = package_dir.as_std_path().join(readme);
+            let Some(template) = readme_template(&destination, package_dir.as_std_path())? else {
+                continue;
+            };
+
+            let mut rustdoc = Table::new();
+            rustdoc.insert("workspace_project".into(), package.name.clone().into());
+            let mut defaults = Table::new();
+            defaults.insert("rustdoc".into(), rustdoc.into());
+
+            let relative = |path: &Path| path.strip_prefix(root).unwrap_or(path).to_path_buf();
+            files.push(FileConfig {
+                template: relative(&template),
+                destination: relative(&destination),
+                defaults,
+                banner: BannerConfig::default(),
+                syntax: None,
+            });
+        }
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            defaults: Table::new(),
+            banner: BannerConfig::default(),
+            syntax: None,
+            files,
+        })
+    }
+
+    fn from_manifest(manifest: &Path) -> Result<Option<Self>> {
+        if !manifest.is_file() {
+            return Ok(None);
+        }
+        let cargo: Table = toml::de::from_str(&fs::read_to_string(manifest)?)
+            .context(format!("failed to parse {manifest:?}"))?;
+
+        let metadata = ["workspace", "package"].iter().find_map(|section| {
+            cargo
+                .get(*section)
+                .and_then(|s| s.get("metadata"))
+                .and_then(|m| m.get("markdown-includes"))
+        });
+
+        match metadata {
+            Some(Value::Table(table)) => {
+                Ok(Some(table.clone().try_into().context(format!(
+                    "failed to parse the metadata of {manifest:?}"
+                ))?))
+            }
+            Some(_) => bail!("the markdown-includes metadata of {manifest:?} is not a table"),
+            None => Ok(None),
+        }
+    }
+}
+
+impl FromStr for ProjectConfig {
+    type Err = anyhow::Error;
+
+    /// Parses a project configuration. The root is left empty, which means
+    /// that paths are relative to the current directory.
+    fn from_str(config: &str) -> Result<Self> {
+        Ok(toml::de::from_str(config)?)
+    }
+}
+
+/// The template of the `readme` of a package in `package_dir`, see
+/// [`ProjectConfig::from_workspace`]
+fn readme_template(readme: &Path, package_dir: &Path) -> Result<Option<PathBuf>> {
+    let Some(stem) = readme.file_stem().and_then(|s| s.to_str()) else {
+        return Ok(None);
+    };
+    let template_name = format!("{stem}.tpl.md");
+    let candidates = [
+        package_dir.join("src").join(&template_name),
+        readme.with_file_name(&template_name),
+    ];
+    if let Some(template) = candidates.into_iter().find(|c| c.is_file()) {
+        return Ok(Some(template));
+    }
+
+    if readme.is_file() && fs::read_to_string(readme)?.contains("<!-- includes:") {
+        return Ok(Some(readme.to_path_buf()));
+    }
+    Ok(None)
+}
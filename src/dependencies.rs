@@ -0,0 +1,48 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+/// The input files read while processing templates: the templates themselves,
+/// the rustdoc sources, the module files visited to resolve intralinks, and
+/// the standard library sources for intralinks into it.
+///
+/// In a _build.rs_, they tell cargo when the documents need to be regenerated:
+///
+/// ```rust,no_run
+/// use markdown_includes::{Mode, Processor};
+///
+/// let processor = Processor::new().mode(Mode::Update);
+/// processor.process("src/README.tpl.md", "README.md").unwrap();
+/// processor.dependencies().emit_rerun_if_changed();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dependencies {
+    paths: BTreeSet<PathBuf>,
+}
+
+impl Dependencies {
+    pub(crate) fn insert(&mut self, path: &Path) {
+        if !self.paths.contains(path) {
+            self.paths.insert(path.to_path_buf());
+        }
+    }
+
+    /// The paths of the input files, sorted and without duplicates
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.paths.iter().map(PathBuf::as_path)
+    }
+
+    /// A `cargo:rerun-if-changed` line for each input file
+    pub fn rerun_if_changed(&self) -> String {
+        self.paths()
+            .map(|path| format!("cargo:rerun-if-changed={}\n", path.display()))
+            .collect()
+    }
+
+    /// Prints the [`Dependencies::rerun_if_changed`] lines to stdout,
+    /// where a build script's instructions to cargo go.
+    pub fn emit_rerun_if_changed(&self) {
+        print!("{}", self.rerun_if_changed());
+    }
+}
@@ -0,0 +1,75 @@
+use std::{fmt, path::PathBuf};
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    /// Fails the fence that reported it, such as a warning
+    /// reported while warnings are denied
+    Error,
+}
+
+/// A message produced while processing a template that does not stop
+/// the processing, such as an intralink that could not be resolved.
+///
+/// The template and fence are filled in by [`crate::fence::FenceContext::report`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// The rust item the diagnostic is about, such as `crate::Missing`
+    pub symbol: Option<String>,
+    /// The template being processed, `None` for a document that is not read from a file
+    pub template: Option<PathBuf>,
+    /// The name of the fence that reported the diagnostic
+    pub fence: Option<String>,
+    /// The line of the template where the fence starts
+    pub line: Option<usize>,
+}
+
+impl Diagnostic {
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, message.into())
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message.into())
+    }
+
+    fn new(severity: Severity, message: String) -> Self {
+        Self {
+            severity,
+            message,
+            symbol: None,
+            template: None,
+            fence: None,
+            line: None,
+        }
+    }
+
+    pub fn with_symbol(mut self, symbol: impl Into<String>) -> Self {
+        self.symbol = Some(symbol.into());
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{severity}: {}", self.message)?;
+
+        let template = self.template.as_ref().map(|t| t.display().to_string());
+        match (&self.fence, template, self.line) {
+            (Some(fence), Some(template), Some(line)) => {
+                write!(f, " (fence `{fence}` at {template}:{line})")
+            }
+            (Some(fence), None, Some(line)) => write!(f, " (fence `{fence}` at line {line})"),
+            (Some(fence), _, None) => write!(f, " (fence `{fence}`)"),
+            (None, Some(template), _) => write!(f, " ({template})"),
+            (None, None, _) => Ok(()),
+        }
+    }
+}
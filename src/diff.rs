@@ -0,0 +1,108 @@
+use std::{fmt, ops::Range};
+
+use serde::Serialize;
+use similar::{ChangeTag, TextDiff};
+
+/// The line differences between two versions of a document,
+/// grouped in hunks with surrounding context lines.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diff {
+    pub hunks: Vec<Hunk>,
+}
+
+/// A group of changed lines together with their context.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Hunk {
+    /// The line number in the old document where the hunk starts, starting at 1,
+    /// or the line before the hunk when it has no lines in the old document
+    pub old_start: usize,
+    pub old_len: usize,
+    /// Same as `old_start`, for the new document
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "line", rename_all = "snake_case")]
+pub enum DiffLine {
+    Context(String),
+    Removed(String),
+    Added(String),
+}
+
+impl Diff {
+    /// The number of unchanged lines shown around each change
+    pub const CONTEXT_LINES: usize = 3;
+
+    /// Compares `old` and `new` line by line, ignoring trailing whitespace.
+    /// Returns `None` if they are the same.
+    pub fn lines(old: &str, new: &str) -> Option<Diff> {
+        let old = trim_line_ends(old);
+        let new = trim_line_ends(new);
+        let diff = TextDiff::from_lines(&old, &new);
+
+        let hunks = diff
+            .grouped_ops(Self::CONTEXT_LINES)
+            .iter()
+            .map(|ops| {
+                let (first, last) = (&ops[0], &ops[ops.len() - 1]);
+                let old_range = first.old_range().start..last.old_range().end;
+                let new_range = first.new_range().start..last.new_range().end;
+                let lines = ops
+                    .iter()
+                    .flat_map(|op| diff.iter_changes(op))
+                    .map(|change| {
+                        let line = change.value().trim_end_matches('\n').to_string();
+                        match change.tag() {
+                            ChangeTag::Equal => DiffLine::Context(line),
+                            ChangeTag::Delete => DiffLine::Removed(line),
+                            ChangeTag::Insert => DiffLine::Added(line),
+                        }
+                    })
+                    .collect();
+                Hunk {
+                    old_start: hunk_start(&old_range),
+                    old_len: old_range.len(),
+                    new_start: hunk_start(&new_range),
+                    new_len: new_range.len(),
+                    lines,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        (!hunks.is_empty()).then_some(Diff { hunks })
+    }
+}
+
+fn hunk_start(range: &Range<usize>) -> usize {
+    match range.is_empty() {
+        true => range.start,
+        false => range.start + 1,
+    }
+}
+
+fn trim_line_ends(doc: &str) -> String {
+    doc.lines().map(|l| format!("{}\n", l.trim_end())).collect()
+}
+
+/// Formats the diff like `diff -u` does, without the file header
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for hunk in &self.hunks {
+            writeln!(
+                f,
+                "@@ -{},{} +{},{} @@",
+                hunk.old_start, hunk.old_len, hunk.new_start, hunk.new_len
+            )?;
+            for line in &hunk.lines {
+                match line {
+                    DiffLine::Context(l) => writeln!(f, " {l}")?,
+                    DiffLine::Removed(l) => writeln!(f, "-{l}")?,
+                    DiffLine::Added(l) => writeln!(f, "+{l}")?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
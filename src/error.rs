@@ -0,0 +1,186 @@
+use std::{
+    fmt,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+/// What a fence does when it fails to generate its content, such as a rustdoc
+/// fence with a missing source file.
+///
+/// ```toml
+/// [defaults.rustdoc]
+/// on_error = "keep-previous"
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorPolicy {
+    /// Fail the template, the default when checking
+    Fail,
+    /// Replace the fence with a visible error, the default otherwise
+    Inline,
+    /// Keep what was generated before: the content between the markers when
+    /// updating in place, or else the output of the fence in the destination file.
+    /// The latter fails when the rest of the destination is out of date, as the
+    /// output of the fence cannot be told apart from it.
+    KeepPrevious,
+}
+
+/// The context of an error of a fence with the [`ErrorPolicy::KeepPrevious`] policy
+#[derive(Debug)]
+pub(crate) struct KeepPrevious;
+
+impl fmt::Display for KeepPrevious {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("keeping the previous content")
+    }
+}
+
+/// Stands for the output of a fence keeping its previous content, in a document
+/// generated from a template until it is filled in from the destination
+pub(crate) const PREVIOUS: &str = "\u{0}previous content\u{0}";
+
+/// `generated` with each [`PREVIOUS`] placeholder replaced with the text found at
+/// its place in `destination`, or `None` if the rest of `generated` differs from
+/// `destination`
+pub(crate) fn fill_previous(generated: &str, destination: &str) -> Option<String> {
+    let mut segments = generated.split(PREVIOUS);
+    let first = segments.next().unwrap_or_default();
+    let mut rest = destination.strip_prefix(first)?;
+    let mut filled = first.to_string();
+    let mut segments = segments.peekable();
+    while let Some(segment) = segments.next() {
+        let end = match segments.peek() {
+            Some(_) => rest.find(segment)?,
+            None => rest.strip_suffix(segment)?.len(),
+        };
+        filled.push_str(&rest[..end]);
+        filled.push_str(segment);
+        rest = &rest[end + segment.len()..];
+    }
+    Some(filled)
+}
+
+/// Where a fence, or the part of its configuration an error is about,
+/// is located in its template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    /// The template, `None` when processing a document that is not read from a file
+    pub template: Option<PathBuf>,
+    /// The byte range in the template
+    pub span: Range<usize>,
+    /// The line the span starts on, starting at 1
+    pub line: usize,
+    /// The character the span starts at in its line, starting at 1
+    pub column: usize,
+    /// The text of the line the span starts on
+    pub source_line: String,
+}
+
+impl Location {
+    pub(crate) fn new(document: &str, span: Range<usize>, template: Option<&Path>) -> Self {
+        let line_start = document[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = document[span.start..]
+            .find('\n')
+            .map_or(document.len(), |i| span.start + i);
+        Self {
+            template: template.map(Path::to_path_buf),
+            line: document[..span.start].matches('\n').count() + 1,
+            column: document[line_start..span.start].chars().count() + 1,
+            source_line: document[line_start..line_end]
+                .trim_end_matches('\r')
+                .to_string(),
+            span,
+        }
+    }
+
+    /// Writes the location as a source snippet, with carets below the span
+    /// <pre>
+    ///  --> README.tpl.md:3:1
+    ///   |
+    /// 3 | max_deph = 2
+    ///   | ^^^^^^^^
+    /// </pre>
+    fn fmt_snippet(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+        let template = self
+            .template
+            .as_ref()
+            .map(|t| format!("{}:", t.display()))
+            .unwrap_or_default();
+        let indent = self
+            .source_line
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        let carets = self
+            .source_line
+            .chars()
+            .skip(self.column - 1)
+            .count()
+            .min(self.span.len())
+            .max(1);
+
+        writeln!(f, "{gutter}--> {template}{}:{}", self.line, self.column)?;
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{} | {}", self.line, self.source_line)?;
+        write!(f, "{gutter} | {indent}{}", "^".repeat(carets))
+    }
+}
+
+/// A fence that could not be created or run.
+#[derive(Debug)]
+pub struct FenceError {
+    /// The fence name, such as `toc`
+    pub fence: String,
+    /// The location of the fence, or of the invalid part of its configuration
+    pub location: Location,
+    pub error: anyhow::Error,
+}
+
+impl fmt::Display for FenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "fence `{}`: {:#}", self.fence, self.error)?;
+        self.location.fmt_snippet(f)
+    }
+}
+
+/// All fence errors of a template. The fences of a template are all
+/// processed even if some of them fail, so that every failure is reported.
+#[derive(Debug, Default)]
+pub struct FenceErrors {
+    pub errors: Vec<FenceError>,
+}
+
+impl FenceErrors {
+    pub(crate) fn push(&mut self, fence: &str, location: &Location, error: anyhow::Error) {
+        self.errors.push(FenceError {
+            fence: fence.to_string(),
+            location: location.clone(),
+            error,
+        })
+    }
+
+    /// Fails with `self` if there are any errors
+    pub(crate) fn into_result(self) -> Result<(), FenceErrors> {
+        match self.errors.is_empty() {
+            true => Ok(()),
+            false => Err(self),
+        }
+    }
+}
+
+impl fmt::Display for FenceErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors = self
+            .errors
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>();
+        write!(f, "{}", errors.join("\n"))
+    }
+}
+
+impl std::error::Error for FenceErrors {}
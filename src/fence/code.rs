@@ -0,0 +1,178 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use super::{Fence, FenceContext, FenceSection};
+use crate::ErrorPolicy;
+
+/// Includes a source file as a code block
+/// <pre>
+/// ```toml code
+/// source = "../examples/basic.rs"
+/// anchor = "setup"
+/// ```
+/// </pre>
+/// The language of the code block is given by the extension of the file.
+/// The marker lines of mdBook's `ANCHOR: name` and `ANCHOR_END: name` regions
+/// are left out, as well as the indentation common to all the included lines.
+pub struct CodeFence {
+    conf: CodeConfig,
+}
+
+#[derive(Deserialize)]
+pub struct CodeConfig {
+    /// The file to include, relative to the template
+    pub source: PathBuf,
+    /// The language of the code block, instead of the one of the file extension
+    pub language: Option<String>,
+    /// The lines to include, numbered from 1, as in `"10-42"`, `"10-"`, `"-42"` or `"10"`
+    pub lines: Option<String>,
+    /// The name of the `ANCHOR: name` ... `ANCHOR_END: name` region to include
+    pub anchor: Option<String>,
+    /// What to do when the file or the selected lines cannot be read
+    #[serde(default)]
+    pub on_error: Option<ErrorPolicy>,
+}
+
+impl Fence for CodeFence {
+    fn name() -> &'static str {
+        "code"
+    }
+
+    fn priority(&self) -> u8 {
+        1
+    }
+
+    fn create(section: &FenceSection, ctx: &FenceContext) -> Result<Box<Self>>
+    where
+        Self: Sized,
+    {
+        let mut conf: CodeConfig = section.parse_config()?;
+        if conf.lines.is_some() && conf.anchor.is_some() {
+            bail!("`lines` and `anchor` cannot be used together");
+        }
+        conf.source = ctx.template_dir.join(&conf.source);
+        Ok(Box::new(Self { conf }))
+    }
+
+    fn run(&self, _document: &str, ctx: &FenceContext) -> Result<String> {
+        self.code_block(ctx)
+            .or_else(|e| ctx.on_error(self.conf.on_error, e))
+    }
+}
+
+impl CodeFence {
+    fn code_block(&self, ctx: &FenceContext) -> Result<String> {
+        let source = &self.conf.source;
+        let content = ctx
+            .read_to_string(source)
+            .context(format!("cannot include {source:?}"))?;
+        let lines = content.lines().collect::<Vec<_>>();
+
+        let selected = match (&self.conf.lines, &self.conf.anchor) {
+            (Some(range), _) => line_range(&lines, range).context(format!("in {source:?}"))?,
+            (_, Some(name)) => anchor(&lines, name).context(format!("in {source:?}"))?,
+            _ => &lines,
+        };
+        let code = dedent(
+            selected
+                .iter()
+                .filter(|line| anchor_marker(line).is_none())
+                .copied(),
+        );
+
+        let language = match &self.conf.language {
+            Some(language) => language.as_str(),
+            None => language(source),
+        };
+        let fence = fence_for(&code);
+        Ok(format!("{fence}{language}\n{code}\n{fence}"))
+    }
+}
+
+/// The lines of a `"10-42"` range, numbered from 1
+fn line_range<'a, 'b>(lines: &'a [&'b str], range: &str) -> Result<&'a [&'b str]> {
+    let parse = |bound: &str, default: usize| -> Result<usize> {
+        match bound.trim() {
+            "" => Ok(default),
+            bound => bound.parse().ok().filter(|&n| n > 0).context(format!(
+                "invalid line range `{range}`, expected one like `10-42`"
+            )),
+        }
+    };
+    let (first, last) = match range.split_once('-') {
+        Some((first, last)) => (parse(first, 1)?, parse(last, lines.len())?),
+        None => (parse(range, 1)?, parse(range, 1)?),
+    };
+    if first > last || last > lines.len() {
+        bail!(
+            "the lines `{range}` are not within the {} lines of the file",
+            lines.len()
+        );
+    }
+    Ok(&lines[first - 1..last])
+}
+
+/// The lines between the `ANCHOR: name` and `ANCHOR_END: name` markers
+fn anchor<'a, 'b>(lines: &'a [&'b str], name: &str) -> Result<&'a [&'b str]> {
+    let start = lines
+        .iter()
+        .position(|line| anchor_marker(line) == Some(("ANCHOR:", name)))
+        .context(format!("no `ANCHOR: {name}` marker"))?;
+    let end = lines[start..]
+        .iter()
+        .position(|line| anchor_marker(line) == Some(("ANCHOR_END:", name)))
+        .context(format!("no `ANCHOR_END: {name}` marker"))?;
+    Ok(&lines[start + 1..start + end])
+}
+
+/// The kind and name of an mdBook anchor marker, such as `// ANCHOR: name`
+fn anchor_marker(line: &str) -> Option<(&'static str, &str)> {
+    ["ANCHOR:", "ANCHOR_END:"].into_iter().find_map(|kind| {
+        let (_, rest) = line.split_once(kind)?;
+        let name = rest
+            .trim_start()
+            .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .next()?;
+        (!name.is_empty()).then_some((kind, name))
+    })
+}
+
+/// Joins the lines, without the indentation they all have in common
+fn dedent<'a>(lines: impl Iterator<Item = &'a str> + Clone) -> String {
+    let indent = lines
+        .clone()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .map(|line| line.get(indent..).unwrap_or("").trim_end_matches('\r'))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The language of a code block for the extension of `path`
+fn language(path: &Path) -> &str {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    match extension {
+        "rs" => "rust",
+        "py" => "python",
+        "js" | "mjs" | "cjs" => "javascript",
+        "ts" => "typescript",
+        "rb" => "ruby",
+        "sh" => "bash",
+        "yml" => "yaml",
+        "md" => "markdown",
+        "h" => "c",
+        "hpp" | "cc" => "cpp",
+        extension => extension,
+    }
+}
+
+/// A code fence longer than any run of backticks in `code`
+fn fence_for(code: &str) -> String {
+    let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
@@ -0,0 +1,164 @@
+use std::ops::Range;
+
+use anyhow::anyhow;
+
+use super::{line_number, locate_error, FenceContext, FenceRegistry, FenceSection, PlacedFence};
+use crate::{markdown::code_blocks, FenceErrors, Location};
+
+pub(super) const BEGIN: &str = "<!-- includes:";
+pub(super) const END: &str = "<!-- /includes";
+const CLOSE: &str = "-->";
+
+/// A comment starting a line with a fence name and configuration
+/// <pre>
+/// &lt;!-- includes:rustdoc source = "lib.rs" --&gt;
+/// </pre>
+pub(super) struct Marker<'a> {
+    pub start: usize,
+    /// The line of the document the marker starts on
+    pub line: usize,
+    pub name: &'a str,
+    pub config: &'a str,
+    /// The offset of the configuration in the document
+    pub config_start: usize,
+    /// The end of the comment, `None` if it is not closed
+    pub end: Option<usize>,
+}
+
+impl Marker<'_> {
+    /// The byte range of the configuration in the document
+    pub fn config_range(&self) -> Range<usize> {
+        self.config_start..self.config_start + self.config.len()
+    }
+}
+
+/// The lines of the document with their byte offset, and whether they are in a code block
+pub(super) struct Lines<'a> {
+    pub lines: Vec<(usize, &'a str)>,
+    code_blocks: Vec<Range<usize>>,
+}
+
+impl<'a> Lines<'a> {
+    pub fn new(document: &'a str) -> Self {
+        let mut start = 0;
+        let lines = document
+            .split_inclusive('\n')
+            .map(|line| {
+                let line_start = start;
+                start += line.len();
+                (line_start, line.trim_end())
+            })
+            .collect();
+        Self {
+            lines,
+            code_blocks: code_blocks(document),
+        }
+    }
+
+    pub fn in_code(&self, offset: usize) -> bool {
+        self.code_blocks.iter().any(|block| block.contains(&offset))
+    }
+
+    /// The lines starting with `prefix` that are not in a code block
+    pub fn starting_with<'b>(&'b self, prefix: &'b str) -> impl Iterator<Item = usize> + 'b {
+        self.lines
+            .iter()
+            .filter(move |(start, line)| line.starts_with(prefix) && !self.in_code(*start))
+            .map(|(start, _)| *start)
+    }
+}
+
+/// The begin markers of `document`, ignoring those in code blocks
+pub(super) fn markers<'a>(document: &'a str, lines: &Lines) -> Vec<Marker<'a>> {
+    lines
+        .starting_with(BEGIN)
+        .map(|start| {
+            let close = document[start..].find(CLOSE).map(|pos| start + pos);
+            let header = &document[start + BEGIN.len()..close.unwrap_or(document.len())];
+            let name_end = header.find(char::is_whitespace).unwrap_or(header.len());
+            let (name, config) = header.split_at(name_end);
+            Marker {
+                start,
+                line: line_number(document, start),
+                name,
+                config,
+                config_start: start + BEGIN.len() + name_end,
+                end: close.map(|close| close + CLOSE.len()),
+            }
+        })
+        .collect()
+}
+
+/// The fence name of an end marker such as `<!-- /includes:toc -->`
+pub(super) fn end_marker_name(line: &str) -> &str {
+    line[END.len()..]
+        .strip_prefix(':')
+        .and_then(|rest| rest.split_whitespace().next())
+        .map_or("", |name| name.trim_end_matches("-->"))
+}
+
+/// Creates the fences of the comment directives of a template, which are
+/// replaced with the generated content, comment included.
+/// <pre>
+/// &lt;!-- includes:toc
+/// header = "# Table of contents"
+/// --&gt;
+/// </pre>
+/// Directives inside code blocks are ignored. Directives that are not closed or
+/// fail to be created are added to `errors`, as well as the end markers of in-place
+/// regions, whose begin markers would otherwise be taken for directives.
+pub(crate) fn find_directives(
+    document: &str,
+    registry: &FenceRegistry,
+    ctx: &FenceContext,
+    errors: &mut FenceErrors,
+) -> Vec<PlacedFence> {
+    let lines = Lines::new(document);
+    let mut directives = Vec::new();
+
+    for start in lines.starting_with(END) {
+        let name = end_marker_name(&document[start..]);
+        let line_end = document[start..]
+            .find('\n')
+            .map_or(document.len(), |i| start + i);
+        let location = Location::new(document, start..line_end, ctx.template);
+        errors.push(
+            name,
+            &location,
+            anyhow!(
+                "end marker of an in-place region in a template, \
+                 in-place regions are only updated in a file that is its own destination"
+            ),
+        );
+    }
+
+    for marker in markers(document, &lines) {
+        let Some(end) = marker.end else {
+            let header_end = document[marker.start..]
+                .find('\n')
+                .map_or(document.len(), |i| marker.start + i);
+            let location = Location::new(document, marker.start..header_end, ctx.template);
+            errors.push(marker.name, &location, anyhow!("unclosed comment"));
+            continue;
+        };
+        let location = Location::new(document, marker.start..end, ctx.template);
+        let section = FenceSection::new(marker.name, marker.config);
+        match registry.create(&section, ctx) {
+            Ok(Some(fence)) => directives.push(PlacedFence {
+                fence,
+                name: marker.name.to_string(),
+                location,
+                outer: marker.start..end,
+                wrap: false,
+                chain: Vec::new(),
+                previous: None,
+            }),
+            Ok(None) => errors.push(marker.name, &location, registry.unknown(marker.name)),
+            Err(e) => {
+                let (location, e) = locate_error(e, location, document, marker.config_range(), ctx);
+                errors.push(marker.name, &location, e)
+            }
+        }
+    }
+    directives
+}
@@ -0,0 +1,90 @@
+use anyhow::{bail, Context, Result};
+
+use super::{
+    comment::{end_marker_name, markers, Lines, END},
+    line_number, locate_error, FenceContext, FenceRegistry, FenceSection, PlacedFence,
+};
+use crate::{FenceErrors, Location};
+
+/// Whether `document` has in-place regions, which is told by their end markers
+/// outside of code blocks.
+pub(crate) fn has_regions(document: &str) -> bool {
+    Lines::new(document).starting_with(END).next().is_some()
+}
+
+/// Finds the regions of a document that is updated in place. A region
+/// starts with a comment holding the fence name and configuration, and
+/// ends with an end marker. Everything in between is generated.
+/// <pre>
+/// &lt;!-- includes:toc
+/// header = "# Table of contents"
+/// --&gt;
+///
+/// - [Generated](#generated)
+///
+/// &lt;!-- /includes:toc --&gt;
+/// </pre>
+/// The end marker has to name the same fence as the marker starting the region,
+/// and regions cannot be nested. Markers inside code blocks are ignored.
+///
+/// Fences that fail to be created are added to `errors`, while malformed
+/// markers fail the whole document.
+pub(crate) fn find_regions(
+    document: &str,
+    registry: &FenceRegistry,
+    ctx: &FenceContext,
+    errors: &mut FenceErrors,
+) -> Result<Vec<PlacedFence>> {
+    let lines = Lines::new(document);
+    let end_markers = lines
+        .starting_with(END)
+        .map(|start| (start, end_marker_name(&document[start..])))
+        .collect::<Vec<_>>();
+    let markers = markers(document, &lines);
+    let mut regions = Vec::new();
+
+    for (i, marker) in markers.iter().enumerate() {
+        let (name, line_no) = (marker.name, marker.line);
+        let close = marker
+            .end
+            .context(format!("unclosed marker comment at line {line_no}"))?;
+
+        let Some(&(end_start, _)) = end_markers
+            .iter()
+            .find(|(start, end_name)| *start >= close && *end_name == name)
+        else {
+            bail!("missing end marker `{END}:{name} -->` for the marker at line {line_no}");
+        };
+        if let Some(next) = markers.get(i + 1).filter(|next| next.start < end_start) {
+            bail!(
+                "the marker at line {} is inside the region of the marker at line {line_no}, \
+                 which ends at line {}",
+                next.line,
+                line_number(document, end_start)
+            );
+        }
+
+        let location = Location::new(document, marker.start..close, ctx.template);
+        let section = FenceSection::new(name, marker.config);
+        match registry.create(&section, ctx) {
+            Ok(Some(fence)) => regions.push(PlacedFence {
+                fence,
+                name: name.to_string(),
+                location,
+                outer: close..end_start,
+                wrap: true,
+                chain: Vec::new(),
+                previous: None,
+            }),
+            Ok(None) => bail!(
+                "in the marker at line {line_no}: {}",
+                registry.unknown(name)
+            ),
+            Err(e) => {
+                let (location, e) = locate_error(e, location, document, marker.config_range(), ctx);
+                errors.push(name, &location, e)
+            }
+        }
+    }
+    Ok(regions)
+}
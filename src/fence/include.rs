@@ -0,0 +1,167 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use super::{Fence, FenceContext, FenceSection};
+use crate::{
+    markdown::{front_matter_len, headings, HeadingBlock},
+    ErrorPolicy,
+};
+
+/// Includes another markdown file, without its front matter
+/// <pre>
+/// ```toml include
+/// source = "../docs/guide.md"
+/// shift_headings = 1
+/// ```
+/// </pre>
+/// The fences of the included file are processed as well, and its headings
+/// are part of the table of contents.
+///
+/// A single section is included with `section = "Usage > Advanced"`, the titles
+/// of the headings leading to it separated by `>`. It goes up to the next heading
+/// of the same or a higher level.
+pub struct IncludeFence {
+    conf: IncludeConfig,
+}
+
+#[derive(Deserialize)]
+pub struct IncludeConfig {
+    /// The file to include, relative to the template
+    pub source: PathBuf,
+    /// The title of the heading of the section to include, or the titles of the
+    /// headings leading to it separated by `>`, such as `"Usage > Advanced"`.
+    /// The section goes up to the next heading of the same or a higher level.
+    pub section: Option<String>,
+    /// Leaves out the heading of the included section, keeping only its content
+    #[serde(default)]
+    pub drop_heading: bool,
+    /// How many levels deeper the headings of the included file are, so that
+    /// they nest under the section of the template including them
+    #[serde(default)]
+    pub shift_headings: usize,
+    /// What to do when the file cannot be read
+    #[serde(default)]
+    pub on_error: Option<ErrorPolicy>,
+}
+
+impl Fence for IncludeFence {
+    fn name() -> &'static str {
+        "include"
+    }
+
+    fn is_match(name: &str) -> bool
+    where
+        Self: Sized,
+    {
+        name.eq_ignore_ascii_case("include") || name.eq_ignore_ascii_case("file")
+    }
+
+    fn priority(&self) -> u8 {
+        1
+    }
+
+    fn create(section: &FenceSection, ctx: &FenceContext) -> Result<Box<Self>>
+    where
+        Self: Sized,
+    {
+        let mut conf: IncludeConfig = section.parse_config()?;
+        conf.source = ctx.template_dir.join(&conf.source);
+        Ok(Box::new(Self { conf }))
+    }
+
+    fn run(&self, _document: &str, ctx: &FenceContext) -> Result<String> {
+        self.include(ctx)
+            .or_else(|e| ctx.on_error(self.conf.on_error, e))
+    }
+
+    fn source(&self) -> Option<&Path> {
+        Some(&self.conf.source)
+    }
+}
+
+impl IncludeFence {
+    fn include(&self, ctx: &FenceContext) -> Result<String> {
+        let content = ctx
+            .read_to_string(&self.conf.source)
+            .context(format!("cannot include {:?}", self.conf.source))?;
+        let content = &content[front_matter_len(&content)..];
+        let content = match &self.conf.section {
+            Some(path) => section(content, path, self.conf.drop_heading)
+                .context(format!("in {:?}", self.conf.source))?,
+            None => content.trim_end_matches(['\r', '\n']),
+        };
+        Ok(shift_headings(content, self.conf.shift_headings))
+    }
+}
+
+/// The section of `document` under the heading at `path`, the titles of the
+/// headings leading to it separated by `>`
+pub fn section<'a>(document: &'a str, path: &str, drop_heading: bool) -> Result<&'a str> {
+    let headings = headings(document);
+    let mut scope = 0..headings.len();
+    let mut found = None;
+    for title in path.split('>').map(str::trim) {
+        let Some(index) = scope.clone().find(|&i| headings[i].title == title) else {
+            bail!(
+                "no section `{path}`, {}",
+                match headings.is_empty() {
+                    true => "there are no headings".to_string(),
+                    false => format!("the headings are: {}", heading_paths(&headings).join(", ")),
+                }
+            );
+        };
+        let depth = headings[index].depth;
+        let end = (index + 1..headings.len())
+            .find(|&i| headings[i].depth <= depth)
+            .unwrap_or(headings.len());
+        scope = index + 1..end;
+        found = Some((index, end));
+    }
+    let (index, end) = found.context("empty section")?;
+
+    let heading = &headings[index].range;
+    let start = match drop_heading {
+        true => heading.end,
+        false => heading.start,
+    };
+    let end = headings.get(end).map_or(document.len(), |h| h.range.start);
+    Ok(document[start..end].trim_matches(['\r', '\n']))
+}
+
+/// The `>` separated path of every heading
+fn heading_paths(headings: &[HeadingBlock]) -> Vec<String> {
+    let mut parents: Vec<&HeadingBlock> = Vec::new();
+    headings
+        .iter()
+        .map(|heading| {
+            while parents.last().is_some_and(|p| p.depth >= heading.depth) {
+                parents.pop();
+            }
+            parents.push(heading);
+            parents
+                .iter()
+                .map(|p| p.title)
+                .collect::<Vec<_>>()
+                .join(" > ")
+        })
+        .collect()
+}
+
+/// Makes the headings of `document` `shift` levels deeper, down to level 6.
+/// Setext headings become ATX headings.
+pub fn shift_headings(document: &str, shift: usize) -> String {
+    let mut shifted = document.to_string();
+    if shift == 0 {
+        return shifted;
+    }
+    for heading in headings(document).into_iter().rev() {
+        let level = (heading.depth + 1 + shift).min(6);
+        shifted.replace_range(
+            heading.range,
+            &format!("{} {}", "#".repeat(level), heading.title),
+        );
+    }
+    shifted
+}
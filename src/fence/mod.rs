@@ -1,14 +1,90 @@
+//! The fences that can be used in a template, and the registry deciding
+//! which of them are available.
+//!
+//! A fence is a TOML code block where the info string has an extra name
+//! after `toml`. The built-in fences are `toc`, `rustdoc`, `include` and `code`,
+//! but any type implementing [`Fence`] can be added to a [`FenceRegistry`]:
+//!
+//! ```rust
+//! use std::path::Path;
+//! use markdown_includes::fence::{Fence, FenceContext, FenceRegistry, FenceSection};
+//! use markdown_includes::Processor;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Shout {
+//!     text: String,
+//! }
+//!
+//! impl Fence for Shout {
+//!     fn name() -> &'static str {
+//!         "shout"
+//!     }
+//!
+//!     fn priority(&self) -> u8 {
+//!         1
+//!     }
+//!
+//!     fn create(section: &FenceSection, _ctx: &FenceContext) -> anyhow::Result<Box<Self>> {
+//!         Ok(Box::new(section.parse_config()?))
+//!     }
+//!
+//!     fn run(&self, _document: &str, _ctx: &FenceContext) -> anyhow::Result<String> {
+//!         Ok(self.text.to_uppercase())
+//!     }
+//! }
+//!
+//! let processor = Processor::new().fences(FenceRegistry::default().with::<Shout>());
+//! let mut document = "```toml shout\ntext = \"hello\"\n```".to_string();
+//! processor.process_document(&mut document, Path::new("")).unwrap();
+//! assert_eq!(document, "HELLO");
+//! ```
+//!
+//! A fence can also be written as an HTML comment directive, which is invisible
+//! when the template itself is rendered, see [`Syntax`]:
+//!
+//! ```markdown
+//! <!-- includes:rustdoc source = "lib.rs" -->
+//! ```
+mod code;
+mod comment;
+mod in_place;
+mod include;
+mod raw;
 mod rustdoc;
+mod strict;
 mod toc;
 
-use std::path::Path;
+use std::{
+    cell::RefCell,
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
-use anyhow::Result;
-use string_sections::{prelude::Sections, SectionSpan};
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use toml::{Table, Value};
 
-use self::{rustdoc::RustDocFence, toc::TocFence};
+use crate::{
+    error::{KeepPrevious, PREVIOUS},
+    markdown::fenced_blocks,
+    processor::parent_dir,
+    Dependencies, Diagnostic, DiskFileSystem, ErrorPolicy, FenceErrors, FileSystem, Location,
+    Severity, Syntax,
+};
+
+pub use self::{code::CodeFence, include::IncludeFence, rustdoc::RustDocFence, toc::TocFence};
+pub(crate) use in_place::{find_regions, has_regions};
+use raw::RawFence;
+use strict::UnknownKey;
 
 pub trait Fence {
+    /// The name the fence is registered under in a [`FenceRegistry`].
+    /// Registering another fence with the same name replaces this one.
+    fn name() -> &'static str
+    where
+        Self: Sized;
+
     /// The fence name is the part after "toml"
     /// <pre>
     /// ```toml name
@@ -17,52 +93,670 @@ pub trait Fence {
     /// </pre>
     /// The name needs to be a minimum of 2 characters long
     ///
+    /// Defaults to a case insensitive comparison with [`Fence::name`].
     fn is_match(name: &str) -> bool
     where
-        Self: Sized;
+        Self: Sized,
+    {
+        name.eq_ignore_ascii_case(Self::name())
+    }
 
     /// At which priority the fence should be executed.
     /// The top priority of 10 is reserved for generating
     /// a table of content
-    fn priority(self: &Self) -> u8;
+    fn priority(&self) -> u8;
 
     /// create a fence
     ///
-    /// - document: the entire document
-    /// - section: a fenced section
-    fn create(document: &str, section: SectionSpan, template_dir: &Path) -> Result<Box<Self>>
+    /// - section: the fenced section, holding the fence name and configuration
+    /// - ctx: the settings of the running processor
+    fn create(section: &FenceSection, ctx: &FenceContext) -> Result<Box<Self>>
     where
         Self: Sized;
 
-    fn run(self: &Self, document: &mut String) -> Result<()>;
+    /// Generate the content replacing the fence.
+    ///
+    /// - document: the entire document, as it looks after all
+    ///   fences with a lower priority have been replaced
+    /// - ctx: the settings of the running processor
+    fn run(&self, document: &str, ctx: &FenceContext) -> Result<String>;
+
+    /// The file the fence includes content from, if any.
+    ///
+    /// The fences in the generated content of such a fence are processed as well,
+    /// with relative paths resolved against the directory of the included file.
+    fn source(&self) -> Option<&Path> {
+        None
+    }
+}
+
+/// What a fence gets to know about the processing it is part of.
+pub struct FenceContext<'a> {
+    /// The directory of the template, which relative paths in
+    /// the fence configuration are resolved against
+    pub template_dir: &'a Path,
+    /// The directory to look for the cargo project in,
+    /// the current directory if `None`
+    pub project_root: Option<&'a Path>,
+    /// The template being processed, for error reporting
+    pub(crate) template: Option<&'a Path>,
+    pub(crate) diagnostics: &'a dyn Fn(&Diagnostic),
+    /// Report warnings as errors, failing the fence
+    pub(crate) deny_warnings: bool,
+    /// The name and line of the running fence, if any
+    pub(crate) fence: Option<(&'a str, usize)>,
+    /// The messages of the errors reported by the running fence
+    pub(crate) reported_errors: RefCell<Vec<String>>,
+    /// Default configurations, keyed by fence name
+    pub(crate) defaults: &'a Table,
+    /// How deep included content containing fences may be nested
+    pub(crate) max_depth: usize,
+    pub(crate) dependencies: &'a RefCell<Dependencies>,
+    pub(crate) fs: &'a dyn FileSystem,
+    /// How fences are written in the template
+    pub(crate) syntax: Syntax,
+    /// Reject unknown fences and configuration keys
+    pub(crate) strict: bool,
+    /// What fences do when they fail, unless configured otherwise
+    pub(crate) on_error: ErrorPolicy,
+    /// Replace the fences keeping their previous content outside of in-place
+    /// regions with [`PREVIOUS`], for the processor to fill in from the destination
+    pub(crate) previous_placeholders: bool,
+}
+
+impl<'a> FenceContext<'a> {
+    /// Reports a diagnostic to the processor's diagnostic sink, filling in the
+    /// template and the running fence.
+    ///
+    /// A diagnostic with [`Severity::Error`], which a warning becomes when warnings
+    /// are denied, fails the running fence.
+    pub fn report(&self, mut diagnostic: Diagnostic) {
+        if diagnostic.template.is_none() {
+            diagnostic.template = self.template.map(Path::to_path_buf);
+        }
+        if let Some((name, line)) = self.fence {
+            diagnostic.fence.get_or_insert_with(|| name.to_string());
+            diagnostic.line.get_or_insert(line);
+        }
+        if self.deny_warnings && diagnostic.severity == Severity::Warning {
+            diagnostic.severity = Severity::Error;
+        }
+        if diagnostic.severity == Severity::Error {
+            self.reported_errors
+                .borrow_mut()
+                .push(diagnostic.message.clone());
+        }
+        (self.diagnostics)(&diagnostic)
+    }
+
+    /// Records a file the generated content depends on, see [`Dependencies`].
+    pub fn add_dependency(&self, path: &Path) {
+        self.dependencies.borrow_mut().insert(path)
+    }
+
+    /// Reads a file through the processor's [`FileSystem`], recording it
+    /// as a dependency.
+    pub fn read_to_string(&self, path: &Path) -> Result<String> {
+        self.add_dependency(path);
+        self.fs.read_to_string(path)
+    }
+
+    /// Handles a failure of the running fence according to `policy`, or to the
+    /// policy of the processor if `None`:
+    /// - [`ErrorPolicy::Fail`] returns the error
+    /// - [`ErrorPolicy::Inline`] returns a `text` block showing the error
+    /// - [`ErrorPolicy::KeepPrevious`] returns the error, which makes the
+    ///   processor keep the previously generated content
+    pub fn on_error(&self, policy: Option<ErrorPolicy>, error: anyhow::Error) -> Result<String> {
+        match policy.unwrap_or(self.on_error) {
+            ErrorPolicy::Fail => Err(error),
+            ErrorPolicy::Inline => {
+                // not a fence, which would be processed again
+                let name = self.fence.map_or("error", |(name, _)| name);
+                Ok(format!("```text\n{name}: {error:#}\n```"))
+            }
+            ErrorPolicy::KeepPrevious => Err(error.context(KeepPrevious)),
+        }
+    }
+
+    /// This context with relative paths resolved against `template_dir`
+    fn with_template_dir<'b>(&'b self, template_dir: &'b Path) -> FenceContext<'b> {
+        FenceContext {
+            template_dir,
+            project_root: self.project_root,
+            template: self.template,
+            diagnostics: self.diagnostics,
+            deny_warnings: self.deny_warnings,
+            fence: None,
+            reported_errors: RefCell::default(),
+            defaults: self.defaults,
+            max_depth: self.max_depth,
+            dependencies: self.dependencies,
+            fs: self.fs,
+            syntax: self.syntax,
+            strict: self.strict,
+            on_error: self.on_error,
+            previous_placeholders: self.previous_placeholders,
+        }
+    }
+}
+
+impl FenceContext<'_> {
+    /// The disk, for the files that are not part of the project such as the
+    /// standard library sources, where reading a file records it as a dependency.
+    pub(crate) fn disk(&self) -> RecordedDisk<'_> {
+        RecordedDisk {
+            dependencies: self.dependencies,
+        }
+    }
+}
+
+/// The processor's file system, where reading a file records it as a dependency.
+impl FileSystem for FenceContext<'_> {
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        FenceContext::read_to_string(self, path)
+    }
+
+    fn write(&self, path: &Path, contents: &str) -> Result<()> {
+        self.fs.write(path, contents)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.fs.is_file(path)
+    }
+}
+
+/// See [`FenceContext::disk`]
+pub(crate) struct RecordedDisk<'a> {
+    pub dependencies: &'a RefCell<Dependencies>,
+}
+
+impl FileSystem for RecordedDisk<'_> {
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        self.dependencies.borrow_mut().insert(path);
+        DiskFileSystem.read_to_string(path)
+    }
+
+    fn write(&self, path: &Path, contents: &str) -> Result<()> {
+        DiskFileSystem.write(path, contents)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        DiskFileSystem.is_file(path)
+    }
+}
+
+/// A fenced section of a template
+/// <pre>
+/// ```toml name
+/// config
+/// ```
+/// </pre>
+#[derive(Clone, Copy)]
+pub struct FenceSection<'a> {
+    /// The name after "toml" on the opening line
+    pub name: &'a str,
+    /// The text between the opening and the closing line
+    pub config: &'a str,
+    defaults: Option<&'a Table>,
+    strict: bool,
+}
+
+impl<'a> FenceSection<'a> {
+    pub(crate) fn new(name: &'a str, config: &'a str) -> Self {
+        Self {
+            name,
+            config,
+            defaults: None,
+            strict: false,
+        }
+    }
+
+    /// Deserializes the TOML configuration of the fence. Keys missing from
+    /// the configuration are taken from the defaults configured for the fence,
+    /// such as the `[defaults.toc]` table of a project configuration.
+    ///
+    /// In strict mode, keys that are not part of `T` are an error.
+    pub fn parse_config<T: DeserializeOwned>(&self) -> Result<T> {
+        if self.defaults.is_none() && !self.strict {
+            return Ok(toml::de::from_str(self.config)?);
+        }
+        let mut config: Table = toml::de::from_str(self.config)?;
+        if let Some(defaults) = self.defaults {
+            merge_defaults(&mut config, defaults);
+        }
+        if self.strict {
+            strict::deserialize(config)
+        } else {
+            Ok(config.try_into()?)
+        }
+    }
+}
+
+/// Adds the keys of `defaults` missing from `table`, recursing into nested tables.
+pub(crate) fn merge_defaults(table: &mut Table, defaults: &Table) {
+    for (key, default) in defaults {
+        match (table.get_mut(key), default) {
+            (None, _) => {
+                table.insert(key.clone(), default.clone());
+            }
+            (Some(Value::Table(nested)), Value::Table(default)) => merge_defaults(nested, default),
+            (Some(_), _) => {}
+        }
+    }
+}
+
+type CreateFn = fn(&FenceSection, &FenceContext) -> Result<Box<dyn Fence>>;
+
+#[derive(Clone)]
+struct FenceKind {
+    name: &'static str,
+    is_match: fn(&str) -> bool,
+    create: CreateFn,
+}
+
+/// The set of fences available when processing a template.
+///
+/// The default registry contains the built-in [`TocFence`], [`RustDocFence`],
+/// [`IncludeFence`] and [`CodeFence`]. Fences are matched against the fence name in
+/// reverse registration order, so that a fence registered later takes
+/// precedence over the ones registered before it.
+#[derive(Clone)]
+pub struct FenceRegistry {
+    kinds: Vec<FenceKind>,
+}
+
+impl Default for FenceRegistry {
+    fn default() -> Self {
+        Self::empty()
+            .with::<TocFence>()
+            .with::<RustDocFence>()
+            .with::<IncludeFence>()
+            .with::<CodeFence>()
+    }
+}
+
+impl FenceRegistry {
+    /// A registry without any fences, not even the built-in ones.
+    pub fn empty() -> Self {
+        Self { kinds: Vec::new() }
+    }
+
+    /// Registers the fence `F`, replacing any fence registered
+    /// under the same name.
+    pub fn register<F: Fence + 'static>(&mut self) -> &mut Self {
+        let kind = FenceKind {
+            name: F::name(),
+            is_match: F::is_match,
+            create: |section, ctx| Ok(F::create(section, ctx)?),
+        };
+        match self.kinds.iter_mut().find(|k| k.name == kind.name) {
+            Some(existing) => *existing = kind,
+            None => self.kinds.insert(0, kind),
+        }
+        self
+    }
+
+    /// Builder version of [`FenceRegistry::register`].
+    pub fn with<F: Fence + 'static>(mut self) -> Self {
+        self.register::<F>();
+        self
+    }
+
+    /// Removes the fence registered under `name`, if any.
+    pub fn remove(&mut self, name: &str) -> &mut Self {
+        self.kinds.retain(|k| k.name != name);
+        self
+    }
+
+    /// Builder version of [`FenceRegistry::remove`].
+    pub fn without(mut self, name: &str) -> Self {
+        self.remove(name);
+        self
+    }
+
+    /// The names of the registered fences, in matching order.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.kinds.iter().map(|k| k.name)
+    }
+
+    fn create(&self, section: &FenceSection, ctx: &FenceContext) -> Result<Option<Box<dyn Fence>>> {
+        match self.kinds.iter().find(|k| (k.is_match)(section.name)) {
+            Some(kind) => {
+                let section = FenceSection {
+                    defaults: ctx.defaults.get(kind.name).and_then(Value::as_table),
+                    strict: ctx.strict,
+                    ..*section
+                };
+                Ok(Some((kind.create)(&section, ctx)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// The error for a fence name that matches none of the registered fences
+    pub(crate) fn unknown(&self, name: &str) -> anyhow::Error {
+        match strict::closest(name, self.names()) {
+            Some(closest) => anyhow!("unknown fence `{name}`, did you mean `{closest}`?"),
+            None => anyhow!(
+                "unknown fence `{name}`, expected one of {}",
+                self.names().collect::<Vec<_>>().join(", ")
+            ),
+        }
+    }
+}
+
+/// A fence together with the byte range it occupies in the document,
+/// fence lines included.
+pub(crate) struct PlacedFence {
+    pub fence: Box<dyn Fence>,
+    /// The fence name, for error reporting
+    pub name: String,
+    /// Where the fence is in the template, for error reporting
+    pub location: Location,
+    pub outer: Range<usize>,
+    /// Surround the output with blank lines, as done between in-place markers
+    pub wrap: bool,
+    /// The files included on the way to this fence, outermost first.
+    /// Empty for the fences of the template itself.
+    pub chain: Vec<PathBuf>,
+    /// The content previously generated in an in-place region
+    pub previous: Option<String>,
+}
+
+impl PlacedFence {
+    /// Moves the fence to account for `replaced` having been substituted
+    /// with a text of `new_len` bytes. Only fences located after the
+    /// replaced range are affected.
+    fn shift(&mut self, replaced: &Range<usize>, new_len: usize) {
+        if self.outer.start >= replaced.end {
+            let start = self.outer.start - replaced.end + replaced.start + new_len;
+            self.outer = start..start + self.outer.len();
+        }
+    }
+}
+
+/// Returns the fence name of the info string of a TOML fence, such as `toc` for "toml toc"
+fn fence_name(info: &str) -> Option<&str> {
+    let mut words = info.split_whitespace();
+    match (words.next(), words.next()) {
+        (Some("toml"), Some(name)) => Some(name),
+        _ => None,
+    }
 }
 
-fn create_fence(
+/// Creates the fences of the document, which are the fenced code blocks
+/// with a `toml <name>` info string that are not nested in another block,
+/// and the comment directives, depending on the [`Syntax`] of the context.
+/// Fences that fail to be created are added to `errors`.
+///
+/// A fence escaped with `toml <name> raw` is emitted as is, without the marker.
+/// In strict mode, a fence with an unknown name is an error.
+pub(crate) fn find_fences(
     document: &str,
-    section: SectionSpan,
-    template_dir: &Path,
-) -> Result<Option<Box<dyn Fence>>> {
-    if TocFence::is_match(&section.start_line) {
-        Ok(Some(TocFence::create(document, section, template_dir)?))
-    } else if RustDocFence::is_match(&section.start_line) {
-        Ok(Some(RustDocFence::create(document, section, template_dir)?))
+    registry: &FenceRegistry,
+    ctx: &FenceContext,
+    errors: &mut FenceErrors,
+) -> Vec<PlacedFence> {
+    let mut fences = Vec::new();
+    if ctx.syntax.comments() {
+        fences = comment::find_directives(document, registry, ctx, errors);
+    }
+    if !ctx.syntax.fences() {
+        return fences;
+    }
+
+    for block in fenced_blocks(document) {
+        let Some(name) = fence_name(block.info) else {
+            continue;
+        };
+        let location = Location::new(document, block.outer.clone(), ctx.template);
+        if RawFence::is_escaped(block.info) {
+            fences.push(PlacedFence {
+                fence: Box::new(RawFence::new(&document[block.outer.clone()], block.info)),
+                name: name.to_string(),
+                location,
+                outer: block.outer,
+                wrap: false,
+                chain: Vec::new(),
+                previous: None,
+            });
+            continue;
+        }
+        let fence_section = FenceSection::new(name, &document[block.inner.clone()]);
+        match registry.create(&fence_section, ctx) {
+            Ok(Some(fence)) => fences.push(PlacedFence {
+                fence,
+                name: name.to_string(),
+                location,
+                outer: block.outer,
+                wrap: false,
+                chain: Vec::new(),
+                previous: None,
+            }),
+            // a TOML block with an info string of its own, unless it looks like a typo
+            Ok(None) if ctx.strict && strict::closest(name, registry.names()).is_some() => {
+                errors.push(name, &location, registry.unknown(name))
+            }
+            Ok(None) => {}
+            Err(e) => {
+                let (location, e) = locate_error(e, location, document, block.inner, ctx);
+                errors.push(name, &location, e)
+            }
+        }
+    }
+    fences
+}
+
+/// The line number of the byte `offset`, starting at 1
+pub(crate) fn line_number(document: &str, offset: usize) -> usize {
+    document[..offset].matches('\n').count() + 1
+}
+
+/// Points a fence creation error at the invalid part of the `config` of the fence,
+/// when it is a TOML error that knows where it is or an unknown key. The snippet of
+/// a TOML error is left out, as it only shows the configuration. Other errors are
+/// located at the fence.
+pub(crate) fn locate_error(
+    error: anyhow::Error,
+    fence: Location,
+    document: &str,
+    config: Range<usize>,
+    ctx: &FenceContext,
+) -> (Location, anyhow::Error) {
+    if error.chain().count() > 1 {
+        return (fence, error);
+    }
+    let (span, error) = if let Some(e) = error.downcast_ref::<toml::de::Error>() {
+        match e.span() {
+            Some(span) => (span, anyhow!("{}", e.message())),
+            None => return (fence, error),
+        }
+    } else if let Some(e) = error.downcast_ref::<UnknownKey>() {
+        match e.span(&document[config.clone()]) {
+            Some(span) => (span, error),
+            None => return (fence, error),
+        }
     } else {
-        Ok(None)
+        return (fence, error);
+    };
+    let span = config.start + span.start..config.start + span.end;
+    (Location::new(document, span, ctx.template), error)
+}
+
+/// Runs the fences in priority order, replacing each of them in the document
+/// with its output.
+///
+/// After each replacement the remaining fences are moved by the change in
+/// length, so their ranges stay valid no matter how many fences there are.
+/// Fences with the same priority are run in document order.
+///
+/// The previously generated content of in-place regions is cleared before
+/// any fence is run, so that it is never seen as part of the document.
+///
+/// The fences in content included from another file are added to the fences
+/// still to run, up to the maximum depth of the context. Including a file that
+/// is already being included is reported as a cycle.
+///
+/// A fence failing to run is left as is in the document and added to `errors`,
+/// unless it keeps the previous content of its in-place region, or is replaced
+/// with a placeholder for its previous content, see [`ErrorPolicy`].
+pub(crate) fn run_fences(
+    document: &mut String,
+    mut fences: Vec<PlacedFence>,
+    registry: &FenceRegistry,
+    ctx: &FenceContext,
+    errors: &mut FenceErrors,
+) {
+    fences.sort_by_key(|f| f.outer.start);
+    for i in (0..fences.len()).rev() {
+        if fences[i].wrap {
+            let outer = fences[i].outer.clone();
+            fences[i].previous = Some(document[outer.clone()].to_string());
+            document.replace_range(outer.clone(), "");
+            fences[i].outer = outer.start..outer.start;
+            for pending in &mut fences[i + 1..] {
+                pending.shift(&outer, 0);
+            }
+        }
+    }
+
+    fences.sort_by_key(|f| (f.fence.priority(), f.outer.start));
+
+    let mut i = 0;
+    while i < fences.len() {
+        let outer = fences[i].outer.clone();
+        let dir = fences[i].chain.last().map(|source| parent_dir(source));
+        let previous = fences[i].previous.take();
+        let mut fence_ctx = ctx.with_template_dir(dir.as_deref().unwrap_or(ctx.template_dir));
+        fence_ctx.fence = Some((&fences[i].name, fences[i].location.line));
+        let result = fences[i]
+            .fence
+            .run(document, &fence_ctx)
+            .and_then(
+                |content| match fence_ctx.reported_errors.take().as_slice() {
+                    [] => Ok(content),
+                    reported => Err(anyhow!("reported errors: {}", reported.join(", "))),
+                },
+            );
+        let (mut content, kept) = match (result, previous) {
+            (Ok(content), _) => (content, false),
+            (Err(e), previous)
+                if e.downcast_ref::<KeepPrevious>().is_some()
+                    && (previous.is_some() || ctx.previous_placeholders) =>
+            {
+                fence_ctx.report(Diagnostic::warning(format!("{e:#}")));
+                // the warning is an error when warnings are denied
+                if !fence_ctx.reported_errors.take().is_empty() {
+                    errors.push(&fences[i].name, &fences[i].location, e);
+                    i += 1;
+                    continue;
+                }
+                (previous.unwrap_or_else(|| PREVIOUS.to_string()), true)
+            }
+            (Err(e), _) => {
+                errors.push(&fences[i].name, &fences[i].location, e);
+                i += 1;
+                continue;
+            }
+        };
+        if fences[i].wrap {
+            content = format!("\n\n{}\n\n", content.trim_matches('\n'));
+        }
+        // the previous content is final, its fences have been run already
+        let nested = match fences[i].fence.source() {
+            Some(source) if !kept => {
+                find_nested(&content, &fences[i], source, registry, ctx, errors)
+            }
+            _ => Vec::new(),
+        };
+        document.replace_range(outer.clone(), &content);
+
+        for pending in &mut fences[i + 1..] {
+            pending.shift(&outer, content.len());
+        }
+        if !nested.is_empty() {
+            fences.extend(nested.into_iter().map(|mut fence| {
+                fence.outer = fence.outer.start + outer.start..fence.outer.end + outer.start;
+                fence
+            }));
+            fences[i + 1..].sort_by_key(|f| (f.fence.priority(), f.outer.start));
+        }
+        i += 1;
     }
 }
 
-pub fn find_fences(document: &str, template_dir: &Path) -> Result<Vec<Box<dyn Fence>>> {
-    let mut fences = Vec::new();
+/// Creates the fences in the `content` that `parent` included from `source`,
+/// with their ranges relative to the content.
+fn find_nested(
+    content: &str,
+    parent: &PlacedFence,
+    source: &Path,
+    registry: &FenceRegistry,
+    ctx: &FenceContext,
+    errors: &mut FenceErrors,
+) -> Vec<PlacedFence> {
+    let mut chain = parent.chain.clone();
+    chain.push(source.to_path_buf());
+    let dir = parent_dir(source);
+    let nested_ctx = ctx.with_template_dir(&dir);
+
+    let mut nested_errors = FenceErrors::default();
+    let mut nested = find_fences(content, registry, &nested_ctx, &mut nested_errors);
+    for error in nested_errors.errors {
+        let context = format!("in the content included from {source:?}");
+        errors.push(&error.fence, &parent.location, error.error.context(context));
+    }
 
-    let section_iter = document.sections(
-        |line| line.starts_with("```"),
-        |sect| sect.end_line.starts_with("```"),
-    );
+    // the include chain starts with the template itself
+    let template = ctx
+        .template
+        .and_then(Path::file_name)
+        .map(|name| ctx.template_dir.join(name));
+    let includes = || template.iter().chain(&chain);
 
-    for section in section_iter {
-        if let Some(fence) = create_fence(document, section, template_dir)? {
-            fences.push(fence)
+    nested.retain(|fence| match fence.fence.source() {
+        Some(nested_source) if includes().any(|s| same_file(ctx, s, nested_source)) => {
+            let cycle = includes()
+                .map(|s| s.as_path())
+                .chain([nested_source])
+                .map(|s| format!("{s:?}"))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            errors.push(
+                &fence.name,
+                &parent.location,
+                anyhow!("include cycle: {cycle}"),
+            );
+            false
         }
+        _ => true,
+    });
+
+    if !nested.is_empty() && chain.len() > ctx.max_depth {
+        let chain = chain
+            .iter()
+            .map(|s| format!("{s:?}"))
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        errors.push(
+            &parent.name,
+            &parent.location,
+            anyhow!(
+                "includes are nested deeper than the maximum depth of {}: {chain}",
+                ctx.max_depth
+            ),
+        );
+        return Vec::new();
     }
-    Ok(fences)
+
+    for fence in &mut nested {
+        fence.location = parent.location.clone();
+        fence.chain = chain.clone();
+    }
+    nested
+}
+
+fn same_file(ctx: &FenceContext, a: &Path, b: &Path) -> bool {
+    ctx.fs.canonicalize(a) == ctx.fs.canonicalize(b)
 }
@@ -0,0 +1,59 @@
+use anyhow::Result;
+
+use super::{Fence, FenceContext, FenceSection};
+
+/// An escaped fence, such as
+/// <pre>
+/// ```toml toc raw
+/// header = "# Table of contents"
+/// ```
+/// </pre>
+/// which is emitted as is, without the `raw` marker, so that documentation
+/// can show fences without them being run.
+pub(super) struct RawFence {
+    text: String,
+}
+
+impl RawFence {
+    /// The marker following the fence name
+    pub const MARKER: &'static str = "raw";
+
+    /// The fence block `block` with `info` as its info string, without the marker
+    pub fn new(block: &str, info: &str) -> Self {
+        let unescaped = info
+            .split_whitespace()
+            .enumerate()
+            .filter(|(i, word)| *i != 2 || *word != Self::MARKER)
+            .map(|(_, word)| word)
+            .collect::<Vec<_>>()
+            .join(" ");
+        Self {
+            text: block.replacen(info, &unescaped, 1),
+        }
+    }
+
+    /// Whether the info string, such as `toml toc raw`, is escaped
+    pub fn is_escaped(info: &str) -> bool {
+        info.split_whitespace().nth(2) == Some(Self::MARKER)
+    }
+}
+
+impl Fence for RawFence {
+    fn name() -> &'static str {
+        "raw"
+    }
+
+    fn priority(&self) -> u8 {
+        0
+    }
+
+    fn create(section: &FenceSection, _ctx: &FenceContext) -> Result<Box<Self>> {
+        Ok(Box::new(Self {
+            text: section.config.to_string(),
+        }))
+    }
+
+    fn run(&self, _document: &str, _ctx: &FenceContext) -> Result<String> {
+        Ok(self.text.clone())
+    }
+}
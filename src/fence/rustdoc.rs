@@ -1,25 +1,31 @@
-use std::{ops::Range, path::Path};
-
 use crate::rustdoc_parse::{parse, RustDocOptions};
 
-use super::Fence;
+use super::{Fence, FenceContext, FenceSection};
 use anyhow::Result;
-use string_sections::SectionSpan;
-
+use std::path::Path;
+
+/// Includes the crate level documentation of a rust source file
+/// <pre>
+/// ```toml rustdoc
+/// source = "lib.rs"
+/// ```
+/// </pre>
 pub struct RustDocFence {
     conf: RustDocOptions,
-    outer: Range<usize>,
 }
 
 impl Fence for RustDocFence {
-    fn create(document: &str, section: SectionSpan, template_dir: &Path) -> Result<Box<Self>>
+    fn name() -> &'static str {
+        "rustdoc"
+    }
+
+    fn create(section: &FenceSection, ctx: &FenceContext) -> Result<Box<Self>>
     where
         Self: Sized,
     {
-        let mut conf: RustDocOptions = toml::de::from_str(&document[section.inner_range()])?;
-        conf.source = template_dir.join(&conf.source);
-        let outer = section.outer_range();
-        Ok(Box::new(Self { conf, outer }))
+        let mut conf: RustDocOptions = section.parse_config()?;
+        conf.source = ctx.template_dir.join(&conf.source);
+        Ok(Box::new(Self { conf }))
     }
 
     fn is_match(name: &str) -> bool
@@ -29,17 +35,22 @@ impl Fence for RustDocFence {
         name.to_lowercase().ends_with("rustdoc")
     }
 
-    fn priority(self: &Self) -> u8 {
+    fn priority(&self) -> u8 {
         1
     }
 
-    fn run(self: &Self, document: &mut String) -> Result<()> {
-        let content = match parse(&self.conf) {
-            Ok(rustdoc) => rustdoc,
-            Err(e) => format!("```toml rustdoc\n{e}\n```"),
-        };
+    fn run(&self, _document: &str, ctx: &FenceContext) -> Result<String> {
+        parse(
+            &self.conf,
+            ctx.project_root,
+            ctx,
+            &ctx.disk(),
+            |diagnostic| ctx.report(diagnostic),
+        )
+        .or_else(|e| ctx.on_error(self.conf.on_error, e))
+    }
 
-        document.replace_range(self.outer.clone(), &content);
-        Ok(())
+    fn source(&self) -> Option<&Path> {
+        Some(&self.conf.source)
     }
 }
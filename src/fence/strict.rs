@@ -0,0 +1,207 @@
+//! Rejecting what a lenient processor ignores, with suggestions for typos.
+use std::{fmt, ops::Range};
+
+use anyhow::{bail, Result};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, Visitor};
+use toml::{Table, Value};
+
+/// The candidate closest to `name`, if it is close enough to be a typo
+pub(super) fn closest<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).clamp(1, 3);
+    candidates
+        .into_iter()
+        .map(|candidate| (strsim::damerau_levenshtein(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Deserializes `config`, failing on the first key that is not a field of `T`
+pub(super) fn deserialize<T: DeserializeOwned>(config: Table) -> Result<T> {
+    let mut unknown = Vec::new();
+    let value: T =
+        serde_ignored::deserialize(Value::Table(config), |path| unknown.push(keys(&path)))?;
+
+    let Some(keys) = unknown.into_iter().next() else {
+        return Ok(value);
+    };
+    let Some((key, parents)) = keys.as_ref().and_then(|keys| keys.split_last()) else {
+        bail!("unknown key");
+    };
+    let parents = parents.iter().map(String::as_str).collect::<Vec<_>>();
+    let suggestion = fields::<T>(&parents).and_then(|fields| closest(key, fields.iter().copied()));
+    Err(UnknownKey {
+        key: keys.unwrap_or_default(),
+        suggestion,
+    }
+    .into())
+}
+
+/// A configuration key that is not a field of the configuration
+#[derive(Debug)]
+pub(super) struct UnknownKey {
+    /// The dotted key, such as `intralinks.strip_link`
+    key: Vec<String>,
+    /// The closest field
+    suggestion: Option<&'static str>,
+}
+
+impl UnknownKey {
+    /// Where the key is set in `config`, either as a dotted key or inside a table
+    pub fn span(&self, config: &str) -> Option<Range<usize>> {
+        let dotted = self.key.join(".");
+        let last = self.key.last()?.clone();
+        [dotted, last].into_iter().find_map(|key| {
+            let mut start = 0;
+            config.split_inclusive('\n').find_map(|line| {
+                let line_start = start;
+                start += line.len();
+                let trimmed = line.trim_start();
+                let rest = trimmed.strip_prefix(key.as_str())?;
+                if rest.starts_with(|c: char| c.is_alphanumeric() || c == '_' || c == '-') {
+                    return None;
+                }
+                let key_start = line_start + line.len() - trimmed.len();
+                Some(key_start..key_start + key.len())
+            })
+        })
+    }
+}
+
+impl fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown key `{}`", self.key.join("."))?;
+        if let Some(suggestion) = self.suggestion {
+            write!(f, ", did you mean `{suggestion}`?")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for UnknownKey {}
+
+/// The table keys leading to an ignored value, `None` if it is in an array
+fn keys(path: &serde_ignored::Path) -> Option<Vec<String>> {
+    use serde_ignored::Path;
+    match path {
+        Path::Root => Some(Vec::new()),
+        Path::Seq { .. } => None,
+        Path::Map { parent, key } => {
+            let mut keys = keys(parent)?;
+            keys.push(key.clone());
+            Some(keys)
+        }
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => keys(parent),
+    }
+}
+
+/// The fields of the struct found by following `path` from `T`, if all of them are structs
+fn fields<T: DeserializeOwned>(path: &[&str]) -> Option<&'static [&'static str]> {
+    match T::deserialize(Fields { path }) {
+        Err(FieldsError::Found(fields)) => Some(fields),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+enum FieldsError {
+    /// Not an error, but the way the fields are returned
+    Found(&'static [&'static str]),
+    Other(String),
+}
+
+impl fmt::Display for FieldsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldsError::Found(fields) => write!(f, "fields {fields:?}"),
+            FieldsError::Other(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for FieldsError {}
+
+impl de::Error for FieldsError {
+    fn custom<M: fmt::Display>(msg: M) -> Self {
+        FieldsError::Other(msg.to_string())
+    }
+}
+
+/// A deserializer walking down the nested structs along `path`, which fails
+/// with the fields of the last one
+struct Fields<'a> {
+    path: &'a [&'a str],
+}
+
+impl<'de> de::Deserializer<'de> for Fields<'_> {
+    type Error = FieldsError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, FieldsError> {
+        Err(de::Error::custom("not a struct"))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FieldsError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, FieldsError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, FieldsError> {
+        match self.path.split_first() {
+            None => Err(FieldsError::Found(fields)),
+            Some((key, path)) => visitor.visit_map(Entry {
+                key: Some(key),
+                path,
+            }),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map enum
+        identifier ignored_any
+    }
+}
+
+/// A table with the single `key`, whose value is deserialized along `path`
+struct Entry<'a> {
+    key: Option<&'a str>,
+    path: &'a [&'a str],
+}
+
+impl<'de> MapAccess<'de> for Entry<'_> {
+    type Error = FieldsError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, FieldsError> {
+        match self.key.take() {
+            Some(key) => seed.deserialize(key.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, FieldsError> {
+        seed.deserialize(Fields { path: self.path })
+    }
+}
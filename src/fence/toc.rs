@@ -1,17 +1,25 @@
 use anyhow::Result;
 use percent_encoding::{percent_encode, CONTROLS};
 use serde::Deserialize;
-use std::{ops::Range, path::Path, str::FromStr};
-use string_sections::SectionSpan;
 
-use super::Fence;
+use super::{Fence, FenceContext, FenceSection};
+use crate::markdown::headings;
 
+/// Generates a table of content from the headings of the document
+/// <pre>
+/// ```toml toc
+/// header = "# Table of contents"
+/// ```
+/// </pre>
 pub struct TocFence {
     conf: TocConfig,
-    outer: Range<usize>,
 }
 
 impl Fence for TocFence {
+    fn name() -> &'static str {
+        "toc"
+    }
+
     fn is_match(name: &str) -> bool
     where
         Self: Sized,
@@ -20,24 +28,23 @@ impl Fence for TocFence {
         name.ends_with("toc") || name.ends_with("table-of-contents")
     }
 
-    fn priority(self: &Self) -> u8 {
+    fn priority(&self) -> u8 {
         10
     }
 
-    fn create(document: &str, section: SectionSpan, _template_dir: &Path) -> Result<Box<Self>>
+    fn create(section: &FenceSection, _ctx: &FenceContext) -> Result<Box<Self>>
     where
         Self: Sized,
     {
-        let conf = toml::de::from_str(&document[section.inner_range()])?;
-        let outer = section.outer_range();
-        Ok(Box::new(Self { conf, outer }))
+        let conf = section.parse_config()?;
+        Ok(Box::new(Self { conf }))
     }
 
-    fn run(self: &Self, document: &mut String) -> Result<()> {
+    fn run(&self, document: &str, _ctx: &FenceContext) -> Result<String> {
         let mut output = String::new();
 
         if let Some(ref header) = self.conf.header {
-            output.push_str(&header);
+            output.push_str(header);
             output.push_str("\n\n");
         }
 
@@ -49,8 +56,7 @@ impl Fence for TocFence {
             .join("\n");
 
         output.push_str(&toc);
-        document.replace_range(self.outer.clone(), &output);
-        Ok(())
+        Ok(output)
     }
 }
 
@@ -82,18 +88,13 @@ fn slugify(text: &str) -> String {
 }
 
 pub fn find_headings(content: &str) -> Vec<Heading> {
-    let mut in_fence = false;
-
-    content
-        .lines()
-        .filter(|line| {
-            let was_inside = in_fence;
-            line.starts_with("```").then(|| in_fence = !in_fence);
-            !(was_inside || in_fence)
+    headings(content)
+        .into_iter()
+        .map(|heading| Heading {
+            depth: heading.depth,
+            title: heading.title.to_string(),
         })
-        .map(Heading::from_str)
-        .filter_map(Result::ok)
-        .collect::<Vec<Heading>>()
+        .collect()
 }
 
 pub struct Heading {
@@ -101,36 +102,6 @@ pub struct Heading {
     pub title: String,
 }
 
-impl FromStr for Heading {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let trimmed = s.trim_end();
-        if trimmed.starts_with("#") {
-            let mut depth = 0usize;
-            let title = trimmed
-                .chars()
-                .skip_while(|c| {
-                    if *c == '#' {
-                        depth += 1;
-                        true
-                    } else {
-                        false
-                    }
-                })
-                .collect::<String>()
-                .trim_start()
-                .to_owned();
-            Ok(Heading {
-                depth: depth - 1,
-                title,
-            })
-        } else {
-            Err(())
-        }
-    }
-}
-
 impl Heading {
     pub fn format(&self, config: &TocConfig) -> Option<String> {
         if self.depth >= config.min_depth
@@ -0,0 +1,149 @@
+use fs_err as fs;
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    path::{Component, Path, PathBuf},
+    rc::Rc,
+};
+
+use anyhow::{bail, Result};
+
+/// Where templates, rust sources and destination files are read from and
+/// written to. Templates can be rendered from buffers that are not saved to
+/// disk, such as an editor's, by giving a [`MemoryFileSystem`] to the
+/// [`crate::Processor`].
+///
+/// The cargo metadata of the project, used by the rustdoc fence to find the
+/// crate name, and the standard library sources are still read from disk.
+pub trait FileSystem {
+    fn read_to_string(&self, path: &Path) -> Result<String>;
+
+    fn write(&self, path: &Path, contents: &str) -> Result<()>;
+
+    fn is_file(&self, path: &Path) -> bool;
+
+    /// The path of the file `path` refers to, which is the same for all the paths
+    /// of a file. Defaults to removing the `.` and resolving the `..` components.
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        normalize(path)
+    }
+}
+
+/// The files on disk.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiskFileSystem;
+
+impl FileSystem for DiskFileSystem {
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        Ok(fs::read_to_string(path)?)
+    }
+
+    fn write(&self, path: &Path, contents: &str) -> Result<()> {
+        Ok(fs::write(path, contents)?)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    /// The absolute path with symbolic links resolved, or the normalized
+    /// path if the file does not exist
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        fs::canonicalize(path).unwrap_or_else(|_| normalize(path))
+    }
+}
+
+/// Files kept in memory. Clones share the same files, so that a clone
+/// can be given to a processor and the written files looked at afterwards.
+///
+/// Paths are compared after removing `.` and resolving `..` components,
+/// without looking at the disk.
+///
+/// ```rust
+/// use std::path::Path;
+/// use markdown_includes::{Banner, MemoryFileSystem, Mode, Processor};
+///
+/// let files = MemoryFileSystem::new().with_file("README.tpl.md", "# Title\n");
+/// Processor::new()
+///     .file_system(files.clone())
+///     .mode(Mode::Update)
+///     .banner(Banner::None)
+///     .process("README.tpl.md", "README.md")
+///     .unwrap();
+/// assert_eq!(files.get("README.md").unwrap(), "# Title\n");
+/// ```
+#[derive(Clone, Default)]
+pub struct MemoryFileSystem {
+    files: Rc<RefCell<BTreeMap<PathBuf, String>>>,
+    /// Where files that are not in memory are read from
+    fallback: Option<Rc<dyn FileSystem>>,
+}
+
+impl MemoryFileSystem {
+    /// An empty file system.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Files in memory on top of the disk: files that are not in memory are
+    /// read from disk, while written files are kept in memory.
+    pub fn overlay() -> Self {
+        Self {
+            files: Default::default(),
+            fallback: Some(Rc::new(DiskFileSystem)),
+        }
+    }
+
+    /// Adds or replaces a file.
+    pub fn insert(&self, path: impl AsRef<Path>, contents: impl Into<String>) {
+        self.files
+            .borrow_mut()
+            .insert(normalize(path.as_ref()), contents.into());
+    }
+
+    /// Builder version of [`MemoryFileSystem::insert`].
+    pub fn with_file(self, path: impl AsRef<Path>, contents: impl Into<String>) -> Self {
+        self.insert(path, contents);
+        self
+    }
+
+    /// The contents of a file kept in memory.
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<String> {
+        self.files.borrow().get(&normalize(path.as_ref())).cloned()
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        match (self.get(path), &self.fallback) {
+            (Some(contents), _) => Ok(contents),
+            (None, Some(fallback)) => fallback.read_to_string(path),
+            (None, None) => bail!("file {path:?} not found in memory"),
+        }
+    }
+
+    fn write(&self, path: &Path, contents: &str) -> Result<()> {
+        self.insert(path, contents);
+        Ok(())
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.files.borrow().contains_key(&normalize(path))
+            || self.fallback.as_ref().is_some_and(|f| f.is_file(path))
+    }
+}
+
+/// Removes the `.` components and resolves the `..` components of `path`
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            c => normalized.push(c),
+        }
+    }
+    normalized
+}
@@ -11,24 +11,24 @@
 //!
 //! ## Example
 //!
-//! _src/README.tpl.md_:
-//! > My title<br>
-//! > <br>
-//! > Include a table of content:<br>
-//! > &#96;&#96;&#96;toml toc<br>
-//! > header = "# Table of contents"<br>
-//! > &#96;&#96;&#96;<br>
-//! > <br>
-//! > Extracted from lib.rs' rust doc:<br>
-//! > <br>
-//! > &#96;&#96;&#96;toml rustdoc<br>
-//! > source = "lib.rs"<br>
-//! > &#96;&#96;&#96;<br>
+//! _src/README.tpl.md_ starts with a title and a table of contents:
 //!
+//! ```toml toc raw
+//! header = "# Table of contents"
+//! ```
+//!
+//! followed by the documentation extracted from _lib.rs_:
+//!
+//! ```toml rustdoc raw
+//! source = "lib.rs"
+//! ```
+//!
+//! A fence is shown as is instead of being run by adding `raw` after its name, as
+//! done above with `toml toc raw`. The `raw` marker is left out of the generated document.
 //!
 //! To generate a _README.md_ file you add a test:
 //!
-//! ```rust
+//! ```rust,ignore
 //! #[test]
 //! fn update_readme() {
 //!     markdown_includes::update("src/README.tpl.md", "README.md").unwrap();
@@ -39,106 +39,251 @@
 //! in a CI pipeline (the CI environment variable is set),
 //! it will fail if the _README.md_ needs updating.
 //!
+//! The mode can also be chosen explicitly with the `MARKDOWN_INCLUDES_MODE`
+//! environment variable, which takes precedence over the CI one:
+//!
+//! - `update`: write the file when it is out of sync
+//! - `check`: fail when the file is out of sync, showing what changed
+//! - `dry-run`: print the generated document to stdout without writing anything
+//! - `bless`: write the file whenever it differs, even if only by whitespace
+//!
+//! ## Comment directives
+//!
+//! A fence can also be written as an HTML comment starting a line, which is hidden
+//! when the template itself is rendered, for instance on GitHub:
+//!
+//! ```markdown
+//! <!-- includes:rustdoc source = "lib.rs" -->
+//!
+//! <!-- includes:toc
+//! header = "# Table of contents"
+//! -->
+//! ```
+//!
+//! The comment is replaced with the generated content. Both syntaxes are recognized
+//! by default, and either one can be chosen with `Processor::syntax` or with the
+//! `syntax` setting of a project or a file, as `"fences"`, `"comments"` or `"both"`.
+//!
+//! ## Including files
+//!
+//! Another markdown file, relative to the template, is inlined with an `include`
+//! fence, also written `file`. Its front matter is left out and its own fences are
+//! processed too:
+//!
+//! ```toml include raw
+//! source = "../docs/guide.md"
+//! shift_headings = 1
+//! ```
+//!
+//! `shift_headings` makes the headings of the included file that many levels
+//! deeper, so that they nest under the section including them. The table of
+//! contents lists the included headings as well.
+//!
+//! `section = "Installation"` includes a single section, up to the next heading
+//! of the same or a higher level. A nested section is found by the titles of the
+//! headings leading to it, as in `section = "Usage > Advanced"`, and
+//! `drop_heading = true` leaves out the heading of the section itself.
+//!
+//! A source file is included as a code block with a `code` fence, its language
+//! given by the file extension unless set with `language`:
+//!
+//! ```toml code raw
+//! source = "../examples/basic.rs"
+//! anchor = "setup"
+//! ```
+//!
+//! Either `lines = "10-42"` or an `anchor` selects part of the file, the latter
+//! being the lines between the mdBook style `ANCHOR: setup` and `ANCHOR_END: setup`
+//! comments. The anchor comments themselves are left out, as well as the
+//! indentation common to the included lines.
+//!
+//! ## In-place
+//!
+//! Instead of a separate template, the includes can be configured with markers
+//! in the _README.md_ itself. The content between a begin and an end marker is
+//! regenerated, everything else is left untouched:
+//!
+//! ```markdown
+//! <!-- includes:toc
+//! header = "# Table of contents"
+//! -->
+//! <!-- /includes:toc -->
+//! ```
+//!
+//! The file is updated with `markdown_includes::update_in_place("README.md")`, or by
+//! calling `update` with the same file as template and destination.
+//!
+//! ## Configuration
+//!
+//! The base directory, project root, mode, banner, diagnostics and available fences
+//! can be configured explicitly with a `Processor`, which can also process several
+//! templates in one go.
+//!
+//! All the templates of a project can also be listed in a _markdown-includes.toml_
+//! file, or in the `[workspace.metadata.markdown-includes]` table of _Cargo.toml_,
+//! together with defaults shared by all fences of a kind:
+//!
+//! ```toml
+//! [defaults.toc]
+//! header = "## Contents"
+//!
+//! [[files]]
+//! template = "src/README.tpl.md"
+//! destination = "README.md"
+//! ```
+//!
+//! and processed with a single `markdown_includes::update_project(".")`.
+//!
+//! The banner asking not to edit the generated document can be replaced, moved
+//! after the front matter of the document, or left out, for the whole project
+//! or for a single file:
+//!
+//! ```toml
+//! [banner]
+//! text = "<!-- Generated from {template}, run `{command}` to update -->"
+//! position = "after-front-matter"
+//!
+//! [[files]]
+//! template = "src/README.tpl.md"
+//! destination = "README.md"
+//! banner.enabled = false
+//! ```
+//!
+//! When a rustdoc fence fails, for instance because its source file is missing,
+//! the check fails. When updating, the error is shown in the document instead.
+//! The `on_error` key of a fence, or `Processor::on_error` for all of them,
+//! chooses between `"fail"`, `"inline"` and `"keep-previous"`, which keeps the
+//! content generated before:
+//!
+//! ```toml
+//! [defaults.rustdoc]
+//! on_error = "fail"
+//! ```
+//!
+//! ## Command line
+//!
+//! The `markdown-includes` binary, installed with
+//! `cargo install markdown-includes --features cli`, drives the same engine
+//! without a test, for template and destination pairs or for the files of a
+//! project configuration:
+//!
+//! ```text
+//! markdown-includes update src/README.tpl.md README.md
+//! markdown-includes check --config markdown-includes.toml
+//! markdown-includes render src/README.tpl.md
+//! markdown-includes watch src/README.tpl.md README.md
+//! ```
+//!
+//! `watch` regenerates a document whenever its template or any file its fences
+//! depend on changes, which is also available as a `Watcher` in the API.
+//! `check` exits with a non-zero status and shows the differences when a file
+//! is out of sync.
+//!
+//! In a cargo workspace, `cargo includes` regenerates the `package.readme` of every
+//! member from its _src/README.tpl.md_, with the rustdoc fences reading the docs of
+//! that member, and `cargo includes --check` makes a single CI step for all of them.
+//!
+//! With `--strict`, or `Processor::strict` in the API, a typo such as `toml tco` or
+//! `max_deph = 2` fails with a suggestion instead of being silently ignored.
+//! Likewise `--deny-warnings`, or `Processor::deny_warnings`, fails a template on
+//! warnings such as an intralink that could not be resolved.
+//!
+//! ## Build scripts
+//!
+//! A `Processor` records the files it reads, so that a _build.rs_ regenerating
+//! the documents only runs again when one of them changes:
+//!
+//! ```rust,ignore
+//! let processor = Processor::new().mode(Mode::Update);
+//! processor.process("src/README.tpl.md", "README.md")?;
+//! processor.dependencies().emit_rerun_if_changed();
+//! ```
+//!
 #[cfg(test)]
 mod tests;
 
-mod fence;
+mod config;
+mod dependencies;
+mod diagnostic;
+mod diff;
+mod error;
+pub mod fence;
+mod file_system;
+mod markdown;
+mod processor;
+mod report;
 mod rustdoc_parse;
+mod watch;
 
-use fs_err as fs;
-use std::{
-    env,
-    iter::zip,
-    path::{Path, PathBuf},
-};
+use std::path::Path;
 
-use anyhow::{bail, Context, Result};
-use fence::find_fences;
+use anyhow::{bail, Result};
 
-pub fn process_includes_document(document: &mut String, template_dir: &Path) -> Result<()> {
-    let mut fences = find_fences(&document, template_dir)?;
-    fences.sort_by_key(|f| f.priority());
+pub use config::{BannerConfig, FileConfig, FileResult, ProjectConfig};
+pub use dependencies::Dependencies;
+pub use diagnostic::{Diagnostic, Severity};
+pub use diff::{Diff, DiffLine, Hunk};
+pub use error::{ErrorPolicy, FenceError, FenceErrors, Location};
+pub use file_system::{DiskFileSystem, FileSystem, MemoryFileSystem};
+pub use processor::{Banner, BannerPosition, Mode, Processor, Syntax};
+pub use report::{FileReport, Report, Status};
+pub use watch::Watcher;
 
-    for fence in fences {
-        fence.run(document)?;
-    }
-    Ok(())
+/// Replaces all fences in `document` with their generated content, using
+/// the default [`Processor`] configuration.
+pub fn process_includes_document(document: &mut String, template_dir: &Path) -> Result<()> {
+    Processor::new().process_document(document, template_dir)
 }
 
+/// Generates `destination_file` from `template_file` using the default
+/// [`Processor`] configuration.
 pub fn update<P1: AsRef<Path>, P2: AsRef<Path>>(
     template_file: P1,
     destination_file: P2,
 ) -> Result<()> {
-    let is_ci = env::var("CI").map(|_| true).unwrap_or(false);
-
-    let template_file = template_file.as_ref();
-
-    let template_dir = template_file
-        .parent()
-        .map(|p| p.to_path_buf())
-        .unwrap_or_else(|| PathBuf::from(""));
-    let mut generated_doc = fs::read_to_string(&template_file)
-        .context(format!(
-            "current working directory: {:?}",
-            env::current_dir()
-        ))
-        .context(format!("failed to read template"))?;
-    process_includes_document(&mut generated_doc, &template_dir)?;
+    Processor::new().process(template_file, destination_file)
+}
 
-    let file = template_file
-        .components()
-        .map(|c| c.as_os_str().to_string_lossy())
-        .collect::<Vec<_>>()
-        .join("/");
-    let generated_doc = format!(
-        r#"<!-- 
-Please don't edit. This document has been generated from {file:?}
---> 
-{generated_doc}"#
-    );
+/// Compares `destination_file` with the document generated from `template_file`
+/// using the default [`Processor`] configuration. Returns `None` if it is in sync.
+pub fn check<P1: AsRef<Path>, P2: AsRef<Path>>(
+    template_file: P1,
+    destination_file: P2,
+) -> Result<Option<Diff>> {
+    Processor::new().check(template_file, destination_file)
+}
 
-    let dest_path = destination_file.as_ref();
+/// Regenerates the regions between in-place markers of `file` using the
+/// default [`Processor`] configuration.
+pub fn update_in_place<P: AsRef<Path>>(file: P) -> Result<()> {
+    Processor::new().process_in_place(file)
+}
 
-    let current_doc = if dest_path.exists() {
-        fs::read_to_string(&dest_path)?
-    } else {
-        "".to_string()
-    };
+/// Processes all files listed in the project configuration found in `dir`, see
+/// [`ProjectConfig`], using the default [`Processor`] configuration.
+/// Fails if any of the files failed.
+pub fn update_project<P: AsRef<Path>>(dir: P) -> Result<()> {
+    let project = ProjectConfig::load(dir)?;
+    let failures = Processor::new()
+        .process_project(&project)
+        .into_iter()
+        .filter_map(|file| {
+            file.result
+                .err()
+                .map(|e| format!("{:?}: {e:#}", file.template))
+        })
+        .collect::<Vec<_>>();
 
-    if let Some(diff_str) = diff(&generated_doc, &current_doc) {
-        if is_ci {
-            bail!(
-                "The markdown document {dest_path:?} is out of sync with {template_file:?}. 
-            Please re-run the tests and commit the updated file. 
-            This message is generated because the test is run on CI (the CI environment variable is set).\n{diff_str}"
-            );
-        } else {
-            fs::write(&dest_path, generated_doc.as_bytes())?;
-        }
+    if !failures.is_empty() {
+        bail!("{}", failures.join("\n"));
     }
-
     Ok(())
 }
 
-fn diff(doc1: &str, doc2: &str) -> Option<String> {
-    if zip(doc1.lines(), doc2.lines()).any(|(l1, l2)| l1.trim() != l2.trim()) {
-        Some(
-            zip(doc1.lines(), doc2.lines())
-                .filter(|(l1, l2)| l1.trim() != l2.trim())
-                .map(|(l1, l2)| format!("> {}\n< {}\n", l1.trim(), l2.trim()))
-                .take(5)
-                .collect::<Vec<_>>()
-                .join(", "),
-        )
-    } else {
-        None
-    }
-}
-
 #[test]
 fn update_readme() {
     update(
-        &Path::new("src").join("README.tpl.md"),
+        Path::new("src").join("README.tpl.md"),
         Path::new("README.md"),
     )
     .unwrap();
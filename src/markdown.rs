@@ -0,0 +1,152 @@
+//! Finding the blocks of a markdown document with a CommonMark parser, so that
+//! `~~~` fences, longer fences, and fences nested in lists, block quotes or
+//! other fences are told apart correctly.
+use std::ops::Range;
+
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag};
+
+/// A fenced code block that is not nested in any other block.
+pub(crate) struct FencedBlock<'a> {
+    /// The info string, such as `toml toc`
+    pub info: &'a str,
+    /// The text between the opening and the closing fence
+    pub inner: Range<usize>,
+    /// The whole block, fences included, without the line break after it
+    pub outer: Range<usize>,
+}
+
+/// A heading that is not nested in any other block.
+pub(crate) struct HeadingBlock<'a> {
+    /// The depth, starting at 0 for a level 1 heading
+    pub depth: usize,
+    /// The source text of the heading, without the `#` markers or underline
+    pub title: &'a str,
+    /// The whole heading, markers or underline included, without the line break after it
+    pub range: Range<usize>,
+}
+
+/// The events of `document` with their range, skipping the front matter
+/// which the parser does not know about
+fn events(document: &str) -> impl Iterator<Item = (Event<'_>, Range<usize>)> {
+    let offset = front_matter_len(document);
+    Parser::new_ext(&document[offset..], Options::all())
+        .into_offset_iter()
+        .map(move |(event, range)| (event, range.start + offset..range.end + offset))
+}
+
+/// The events of `document` that start a block not nested in any other block
+fn top_level_starts(document: &str) -> impl Iterator<Item = (Tag<'_>, Range<usize>)> {
+    let mut depth = 0usize;
+    events(document).filter_map(move |(event, range)| match event {
+        Event::Start(tag) => {
+            depth += 1;
+            (depth == 1).then_some((tag, range))
+        }
+        Event::End(_) => {
+            depth -= 1;
+            None
+        }
+        _ => None,
+    })
+}
+
+/// The top level fenced code blocks of `document` that have a closing fence.
+pub(crate) fn fenced_blocks(document: &str) -> Vec<FencedBlock<'_>> {
+    top_level_starts(document)
+        .filter_map(|(tag, range)| match tag {
+            Tag::CodeBlock(CodeBlockKind::Fenced(_)) => fenced_block(document, range),
+            _ => None,
+        })
+        .collect()
+}
+
+fn fenced_block(document: &str, range: Range<usize>) -> Option<FencedBlock<'_>> {
+    let block = document[range.clone()].trim_end_matches(['\n', '\r']);
+    let opening_end = block.find('\n')?;
+    let opening = block[..opening_end].trim();
+    let fence_len = opening
+        .chars()
+        .take_while(|c| *c == '`' || *c == '~')
+        .count();
+    let fence = &opening[..fence_len];
+
+    let closing_start = block.rfind('\n')? + 1;
+    let closing = block[closing_start..].trim();
+    // a block without closing fence runs until the end of the document
+    if closing_start <= opening_end || !closing.starts_with(fence) {
+        return None;
+    }
+
+    let inner_start = range.start + opening_end + 1;
+    Some(FencedBlock {
+        info: opening[fence_len..].trim(),
+        inner: inner_start..(range.start + closing_start).max(inner_start),
+        outer: range.start..range.start + block.len(),
+    })
+}
+
+/// The top level headings of `document`.
+pub(crate) fn headings(document: &str) -> Vec<HeadingBlock<'_>> {
+    top_level_starts(document)
+        .filter_map(|(tag, range)| match tag {
+            Tag::Heading(level, ..) => Some(HeadingBlock {
+                depth: level as usize - 1,
+                title: heading_title(&document[range.clone()]),
+                range: range.start..range.start + document[range].trim_end().len(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The text of an ATX (`## Title`) or setext (`Title` underlined) heading
+fn heading_title(source: &str) -> &str {
+    let source = source.trim();
+    match source.strip_prefix('#') {
+        Some(_) => {
+            let title = source.trim_start_matches('#');
+            // an optional closing sequence of `#`s
+            let without_closing = title.trim_end_matches('#');
+            match without_closing.ends_with([' ', '\t']) || without_closing.is_empty() {
+                true => without_closing.trim(),
+                false => title.trim(),
+            }
+        }
+        None => source
+            .rsplit_once('\n')
+            .map_or(source, |(title, _)| title)
+            .trim(),
+    }
+}
+
+/// The ranges of all code blocks of `document`, at any depth.
+pub(crate) fn code_blocks(document: &str) -> Vec<Range<usize>> {
+    events(document)
+        .filter_map(|(event, range)| match event {
+            Event::Start(Tag::CodeBlock(_)) => Some(range),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The length in bytes of the YAML or TOML front matter the document starts with,
+/// closing line included, or 0 if there is none
+pub(crate) fn front_matter_len(document: &str) -> usize {
+    let mut lines = document.split_inclusive('\n');
+    let Some(first) = lines.next() else {
+        return 0;
+    };
+    let delimiter = first.trim_end();
+    if delimiter != "---" && delimiter != "+++" {
+        return 0;
+    }
+
+    let mut len = first.len();
+    for line in lines {
+        len += line.len();
+        if line.trim_end() == delimiter {
+            return len;
+        }
+    }
+    0
+}
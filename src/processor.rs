@@ -0,0 +1,837 @@
+use std::{
+    cell::RefCell,
+    env,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use toml::Table;
+
+use crate::{
+    config::{BannerConfig, FileResult, ProjectConfig},
+    diff::Diff,
+    error::{fill_previous, PREVIOUS},
+    fence::{
+        find_fences, find_regions, has_regions, merge_defaults, run_fences, FenceContext,
+        FenceRegistry,
+    },
+    markdown::front_matter_len,
+    report::{FileReport, Report, Status},
+    Dependencies, Diagnostic, DiskFileSystem, ErrorPolicy, FenceErrors, FileSystem,
+};
+
+/// What [`Processor::process`] does with the generated document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Write the destination file when it is out of sync with the template
+    Update,
+    /// Fail when the destination file is out of sync with the template
+    Check,
+    /// Print the generated document to stdout without writing anything
+    DryRun,
+    /// Write the destination file whenever it differs from the generated
+    /// document, even if only by whitespace
+    Bless,
+}
+
+impl Mode {
+    /// The environment variable selecting the mode, with a value of
+    /// `update`, `check`, `dry-run` or `bless`
+    pub const ENV_VAR: &'static str = "MARKDOWN_INCLUDES_MODE";
+
+    /// The mode selected by the [`Mode::ENV_VAR`] environment variable. If it is not
+    /// set, [`Mode::Check`] when the CI environment variable is set and [`Mode::Update`]
+    /// otherwise.
+    pub fn from_env() -> Result<Self> {
+        match env::var(Self::ENV_VAR) {
+            Ok(mode) => mode
+                .parse()
+                .context(format!("invalid {} environment variable", Self::ENV_VAR)),
+            Err(env::VarError::NotPresent) if env::var("CI").is_ok() => Ok(Mode::Check),
+            Err(env::VarError::NotPresent) => Ok(Mode::Update),
+            Err(e) => Err(e).context(format!("invalid {} environment variable", Self::ENV_VAR)),
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = anyhow::Error;
+
+    fn from_str(mode: &str) -> Result<Self> {
+        match mode.trim().to_ascii_lowercase().as_str() {
+            "update" => Ok(Mode::Update),
+            "check" => Ok(Mode::Check),
+            "dry-run" | "dry_run" | "dryrun" => Ok(Mode::DryRun),
+            "bless" => Ok(Mode::Bless),
+            _ => bail!("unknown mode `{mode}`, expected one of update, check, dry-run or bless"),
+        }
+    }
+}
+
+/// Whether the generated document starts with a comment asking not to edit it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Banner {
+    /// A comment naming the template the document was generated from
+    Generated,
+    /// A custom text, where `{template}` is replaced with the path of the
+    /// template and `{command}` with the command regenerating the document
+    Custom(String),
+    /// No banner at all
+    None,
+}
+
+/// Where the banner is placed in the generated document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BannerPosition {
+    /// On the first line
+    Top,
+    /// After the YAML (`---`) or TOML (`+++`) front matter the document
+    /// starts with, as used by mdBook, Zola and Jekyll. On the first line
+    /// if there is none.
+    AfterFrontMatter,
+}
+
+/// How the fences of a template are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Syntax {
+    /// TOML code blocks with the fence name after `toml`
+    /// <pre>
+    /// ```toml rustdoc
+    /// source = "lib.rs"
+    /// ```
+    /// </pre>
+    Fences,
+    /// HTML comments starting a line, with the fence name after `includes:`.
+    /// They are hidden when the template itself is rendered.
+    /// <pre>
+    /// &lt;!-- includes:rustdoc source = "lib.rs" --&gt;
+    /// </pre>
+    Comments,
+    /// Both fences and comments
+    #[default]
+    Both,
+}
+
+impl Syntax {
+    pub(crate) fn fences(self) -> bool {
+        self != Syntax::Comments
+    }
+
+    pub(crate) fn comments(self) -> bool {
+        self != Syntax::Fences
+    }
+}
+
+/// Processes templates with an explicit configuration.
+///
+/// ```rust,no_run
+/// use markdown_includes::{Mode, Processor};
+///
+/// Processor::new()
+///     .mode(Mode::Check)
+///     .process_all([
+///         ("src/README.tpl.md", "README.md"),
+///         ("docs/guide.tpl.md", "docs/guide.md"),
+///     ])
+///     .unwrap();
+/// ```
+pub struct Processor {
+    base_dir: PathBuf,
+    project_root: Option<PathBuf>,
+    /// Taken from the environment when `None`
+    mode: Option<Mode>,
+    options: TemplateOptions,
+    diagnostics: Box<dyn Fn(&Diagnostic)>,
+    fences: FenceRegistry,
+    max_depth: usize,
+    strict: bool,
+    deny_warnings: bool,
+    dependencies: RefCell<Dependencies>,
+    fs: Box<dyn FileSystem>,
+}
+
+/// The settings that can differ between the templates of a project.
+#[derive(Clone)]
+pub(crate) struct TemplateOptions {
+    pub banner: Banner,
+    pub banner_position: BannerPosition,
+    /// Replaces `{command}` in a custom banner
+    pub command: String,
+    /// The directory the template path in the banner is relative to
+    pub template_root: PathBuf,
+    /// Default fence configurations, keyed by fence name
+    pub defaults: Table,
+    pub syntax: Syntax,
+    /// [`ErrorPolicy::Fail`] when checking and [`ErrorPolicy::Inline`] otherwise if `None`
+    pub on_error: Option<ErrorPolicy>,
+    /// See [`FenceContext::previous_placeholders`]
+    pub previous_placeholders: bool,
+}
+
+impl TemplateOptions {
+    /// These options with the fence defaults replaced by `defaults`, the banner
+    /// settings by `banner` and the syntax by `syntax`, falling back to the
+    /// current ones where missing.
+    pub fn overridden_by(
+        &self,
+        defaults: &Table,
+        banner: &BannerConfig,
+        syntax: Option<Syntax>,
+    ) -> Self {
+        let mut defaults = defaults.clone();
+        merge_defaults(&mut defaults, &self.defaults);
+
+        let banner_text = match (banner.enabled, &banner.text) {
+            (Some(false), _) => Some(Banner::None),
+            (_, Some(text)) => Some(Banner::Custom(text.clone())),
+            (Some(true), None) if self.banner == Banner::None => Some(Banner::Generated),
+            _ => None,
+        };
+        Self {
+            defaults,
+            banner: banner_text.unwrap_or_else(|| self.banner.clone()),
+            banner_position: banner.position.unwrap_or(self.banner_position),
+            command: banner
+                .command
+                .clone()
+                .unwrap_or_else(|| self.command.clone()),
+            template_root: self.template_root.clone(),
+            syntax: syntax.unwrap_or(self.syntax),
+            on_error: self.on_error,
+            previous_placeholders: self.previous_placeholders,
+        }
+    }
+}
+
+impl Default for Processor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor {
+    /// A processor with the default configuration:
+    /// - paths are relative to the current directory
+    /// - the cargo project is looked up from the current directory
+    /// - the mode is read from the environment when processing, see [`Mode::from_env`]
+    /// - a [`Banner::Generated`] banner at the [`BannerPosition::Top`]
+    /// - diagnostics are printed to stdout
+    /// - the built-in fences, written as TOML fences or comment directives
+    /// - fences in included content are processed 8 levels deep
+    /// - unknown fences and configuration keys are ignored
+    /// - failing fences fail the template when checking, and show the error otherwise
+    /// - files are read from and written to disk
+    pub fn new() -> Self {
+        Self {
+            base_dir: PathBuf::new(),
+            project_root: None,
+            mode: None,
+            options: TemplateOptions {
+                banner: Banner::Generated,
+                banner_position: BannerPosition::Top,
+                command: "cargo test".to_string(),
+                template_root: PathBuf::new(),
+                defaults: Table::new(),
+                syntax: Syntax::Both,
+                on_error: None,
+                previous_placeholders: false,
+            },
+            diagnostics: Box::new(|diagnostic| println!("{diagnostic}")),
+            fences: FenceRegistry::default(),
+            max_depth: 8,
+            strict: false,
+            deny_warnings: false,
+            dependencies: RefCell::default(),
+            fs: Box::new(DiskFileSystem),
+        }
+    }
+
+    /// The directory that template and destination paths are relative to.
+    pub fn base_dir(mut self, base_dir: impl Into<PathBuf>) -> Self {
+        self.base_dir = base_dir.into();
+        self
+    }
+
+    /// The directory to look for the cargo project in, used by the rustdoc fence.
+    pub fn project_root(mut self, project_root: impl Into<PathBuf>) -> Self {
+        self.project_root = Some(project_root.into());
+        self
+    }
+
+    /// Sets the mode, ignoring the environment.
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    pub fn banner(mut self, banner: Banner) -> Self {
+        self.options.banner = banner;
+        self
+    }
+
+    pub fn banner_position(mut self, position: BannerPosition) -> Self {
+        self.options.banner_position = position;
+        self
+    }
+
+    /// The command regenerating the documents, replacing `{command}`
+    /// in a [`Banner::Custom`]. Defaults to `cargo test`.
+    pub fn regenerate_command(mut self, command: impl Into<String>) -> Self {
+        self.options.command = command.into();
+        self
+    }
+
+    /// Default fence configurations, keyed by fence name. Keys missing from a
+    /// fence's configuration are taken from here.
+    ///
+    /// ```toml
+    /// [toc]
+    /// bullet = "*"
+    ///
+    /// [rustdoc.intralinks]
+    /// strip_links = true
+    /// ```
+    pub fn defaults(mut self, defaults: Table) -> Self {
+        self.options.defaults = defaults;
+        self
+    }
+
+    /// Where diagnostics, such as unresolved intralinks, are sent.
+    pub fn diagnostics(mut self, sink: impl Fn(&Diagnostic) + 'static) -> Self {
+        self.diagnostics = Box::new(sink);
+        self
+    }
+
+    /// What fences do when they fail, such as a rustdoc fence with a missing source
+    /// file, unless configured otherwise with the `on_error` key of the fence.
+    /// Defaults to [`ErrorPolicy::Fail`] when checking and to [`ErrorPolicy::Inline`]
+    /// otherwise.
+    pub fn on_error(mut self, policy: ErrorPolicy) -> Self {
+        self.options.on_error = Some(policy);
+        self
+    }
+
+    /// Reports warnings, such as unresolved intralinks, as errors that fail
+    /// the template, so that they fail a check on CI instead of going unnoticed.
+    pub fn deny_warnings(mut self, deny_warnings: bool) -> Self {
+        self.deny_warnings = deny_warnings;
+        self
+    }
+
+    /// How the fences of the templates are written, both syntaxes by default.
+    pub fn syntax(mut self, syntax: Syntax) -> Self {
+        self.options.syntax = syntax;
+        self
+    }
+
+    /// The fences available in the templates.
+    pub fn fences(mut self, fences: FenceRegistry) -> Self {
+        self.fences = fences;
+        self
+    }
+
+    /// Where templates and sources are read from and generated documents written to.
+    pub fn file_system(mut self, fs: impl FileSystem + 'static) -> Self {
+        self.fs = Box::new(fs);
+        self
+    }
+
+    /// How many levels deep fences in included content are processed, such as a
+    /// `toml toc` fence in the crate documentation included by a rustdoc fence.
+    /// Deeper nesting is an error, and 0 means that no included content may
+    /// contain fences.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// In strict mode, a `toml <name>` fence with a name close to one of the
+    /// registered fences and a key that is not part of a fence's configuration are
+    /// errors, suggesting the closest valid name or key:
+    /// <pre>
+    /// fence `tco`: unknown fence `tco`, did you mean `toc`?
+    ///  --> README.tpl.md:3:1
+    ///   |
+    /// 3 | ```toml tco
+    ///   | ^^^^^^^^^^^
+    /// </pre>
+    /// Otherwise such fences are left as they are and such keys are ignored. Other
+    /// TOML blocks, such as ```` ```toml title ````, are always left as they are.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Replaces all fences in `document` with their generated content.
+    ///
+    /// - template_dir: the directory relative paths in fences are resolved against
+    pub fn process_document(&self, document: &mut String, template_dir: &Path) -> Result<()> {
+        self.process_document_with(document, template_dir, None, &self.options)
+    }
+
+    fn process_document_with(
+        &self,
+        document: &mut String,
+        template_dir: &Path,
+        template: Option<&Path>,
+        options: &TemplateOptions,
+    ) -> Result<()> {
+        let template_dir = self.base_dir.join(template_dir);
+        let ctx = self.fence_context(&template_dir, template, options);
+        let mut errors = FenceErrors::default();
+        let fences = find_fences(document, &self.fences, &ctx, &mut errors);
+        run_fences(document, fences, &self.fences, &ctx, &mut errors);
+        Ok(errors.into_result()?)
+    }
+
+    /// Regenerates the content between the in-place markers of `document`,
+    /// leaving everything else untouched.
+    /// <pre>
+    /// &lt;!-- includes:toc
+    /// header = "# Table of contents"
+    /// --&gt;
+    /// generated content
+    /// &lt;!-- /includes:toc --&gt;
+    /// </pre>
+    ///
+    /// - dir: the directory relative paths in markers are resolved against
+    pub fn process_document_in_place(&self, document: &mut String, dir: &Path) -> Result<()> {
+        self.process_document_in_place_with(document, dir, None, &self.options)
+    }
+
+    fn process_document_in_place_with(
+        &self,
+        document: &mut String,
+        dir: &Path,
+        file: Option<&Path>,
+        options: &TemplateOptions,
+    ) -> Result<()> {
+        let dir = self.base_dir.join(dir);
+        let ctx = self.fence_context(&dir, file, options);
+        let mut errors = FenceErrors::default();
+        let regions = find_regions(document, &self.fences, &ctx, &mut errors)?;
+        run_fences(document, regions, &self.fences, &ctx, &mut errors);
+        Ok(errors.into_result()?)
+    }
+
+    fn fence_context<'a>(
+        &'a self,
+        template_dir: &'a Path,
+        template: Option<&'a Path>,
+        options: &'a TemplateOptions,
+    ) -> FenceContext<'a> {
+        FenceContext {
+            template_dir,
+            project_root: self.project_root.as_deref(),
+            template,
+            diagnostics: &*self.diagnostics,
+            deny_warnings: self.deny_warnings,
+            fence: None,
+            reported_errors: RefCell::default(),
+            defaults: &options.defaults,
+            max_depth: self.max_depth,
+            dependencies: &self.dependencies,
+            fs: &*self.fs,
+            syntax: options.syntax,
+            strict: self.strict,
+            on_error: options.on_error.unwrap_or(ErrorPolicy::Inline),
+            previous_placeholders: options.previous_placeholders,
+        }
+    }
+
+    /// Generates the document for `template_file`, banner included. A file with
+    /// in-place regions is processed in place instead, see [`Processor::process_in_place`].
+    pub fn render<P: AsRef<Path>>(&self, template_file: P) -> Result<String> {
+        let template_file = template_file.as_ref();
+        match has_regions(&self.read_template(template_file)?) {
+            true => self.generate_with(template_file, template_file, &self.options, false),
+            false => self.render_with(template_file, &self.options),
+        }
+    }
+
+    fn render_with(&self, template_file: &Path, options: &TemplateOptions) -> Result<String> {
+        let mut generated_doc = self.read_template(template_file)?;
+        self.process_document_with(
+            &mut generated_doc,
+            &parent_dir(template_file),
+            Some(template_file),
+            options,
+        )?;
+
+        let file = template_file
+            .strip_prefix(&options.template_root)
+            .unwrap_or(template_file)
+            .components()
+            .filter(|c| *c != Component::CurDir)
+            .map(|c| match c {
+                Component::RootDir => "".into(),
+                c => c.as_os_str().to_string_lossy(),
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        let banner = match &options.banner {
+            Banner::None => return Ok(generated_doc),
+            Banner::Generated => format!(
+                r#"<!-- 
+Please don't edit. This document has been generated from {file:?}
+--> 
+"#
+            ),
+            Banner::Custom(text) => {
+                let mut banner = text
+                    .replace("{template}", &file)
+                    .replace("{command}", &options.command);
+                if !banner.ends_with('\n') {
+                    banner.push('\n');
+                }
+                banner
+            }
+        };
+
+        let at = match options.banner_position {
+            BannerPosition::Top => 0,
+            BannerPosition::AfterFrontMatter => front_matter_len(&generated_doc),
+        };
+        generated_doc.insert_str(at, &banner);
+        Ok(generated_doc)
+    }
+
+    /// The input files read by this processor so far, to find out when the
+    /// generated documents need to be regenerated.
+    pub fn dependencies(&self) -> Dependencies {
+        self.dependencies.borrow().clone()
+    }
+
+    /// The input files read so far, forgetting about them
+    pub(crate) fn take_dependencies(&self) -> Dependencies {
+        self.dependencies.take()
+    }
+
+    /// The options used for templates processed with [`Processor::process`]
+    pub(crate) fn options(&self) -> &TemplateOptions {
+        &self.options
+    }
+
+    fn read_template(&self, template_file: &Path) -> Result<String> {
+        let path = self.base_dir.join(template_file);
+        self.dependencies.borrow_mut().insert(&path);
+        self.fs
+            .read_to_string(&path)
+            .context(format!(
+                "current working directory: {:?}",
+                env::current_dir()
+            ))
+            .context("failed to read template")
+    }
+
+    /// Generates the document for `template_file` and, depending on the [`Mode`],
+    /// writes it to `destination_file`, checks that `destination_file` is in sync
+    /// or prints it to stdout.
+    ///
+    /// When both are the same file, it is processed in place, see
+    /// [`Processor::process_in_place`].
+    pub fn process<P1: AsRef<Path>, P2: AsRef<Path>>(
+        &self,
+        template_file: P1,
+        destination_file: P2,
+    ) -> Result<()> {
+        self.process_with(
+            template_file.as_ref(),
+            destination_file.as_ref(),
+            &self.options,
+        )
+    }
+
+    pub(crate) fn process_with(
+        &self,
+        template_file: &Path,
+        destination_file: &Path,
+        options: &TemplateOptions,
+    ) -> Result<()> {
+        let mode = match self.mode {
+            Some(mode) => mode,
+            None => Mode::from_env()?,
+        };
+        let checking = mode == Mode::Check;
+        let generated_doc =
+            self.generate_with(template_file, destination_file, options, checking)?;
+        self.output(template_file, destination_file, &generated_doc, mode)
+    }
+
+    /// Regenerates the regions between in-place markers of `file`, and
+    /// depending on the [`Mode`] writes the file back, checks that it is
+    /// in sync or prints it to stdout. No banner is added.
+    pub fn process_in_place<P: AsRef<Path>>(&self, file: P) -> Result<()> {
+        let file = file.as_ref();
+        self.process_with(file, file, &self.options)
+    }
+
+    /// Compares `destination_file` with the document generated from `template_file`,
+    /// without writing anything. Returns `None` if it is in sync.
+    ///
+    /// A missing destination file is compared as an empty document.
+    pub fn check<P1: AsRef<Path>, P2: AsRef<Path>>(
+        &self,
+        template_file: P1,
+        destination_file: P2,
+    ) -> Result<Option<Diff>> {
+        let destination_file = destination_file.as_ref();
+        let generated_doc = self.generate_with(
+            template_file.as_ref(),
+            destination_file,
+            &self.options,
+            true,
+        )?;
+        let current_doc = self.read_destination(destination_file)?;
+        Ok(Diff::lines(
+            current_doc.as_deref().unwrap_or(""),
+            &generated_doc,
+        ))
+    }
+
+    /// Compares `destination_file` with the document generated from `template_file`,
+    /// without writing anything. Unlike [`Processor::check`], failures are part of
+    /// the returned report.
+    pub fn check_file<P1: AsRef<Path>, P2: AsRef<Path>>(
+        &self,
+        template_file: P1,
+        destination_file: P2,
+    ) -> FileReport {
+        self.check_file_with(
+            template_file.as_ref(),
+            destination_file.as_ref(),
+            &self.options,
+        )
+    }
+
+    fn check_file_with(
+        &self,
+        template_file: &Path,
+        destination_file: &Path,
+        options: &TemplateOptions,
+    ) -> FileReport {
+        let compared = self
+            .generate_with(template_file, destination_file, options, true)
+            .and_then(|doc| Ok((doc, self.read_destination(destination_file)?)));
+
+        let status = match compared {
+            Err(e) => Status::failed(e),
+            Ok((_, None)) => Status::WouldCreate,
+            Ok((generated_doc, Some(current_doc))) => {
+                match Diff::lines(&current_doc, &generated_doc) {
+                    None => Status::UpToDate,
+                    Some(diff) => Status::WouldChange(diff),
+                }
+            }
+        };
+        FileReport {
+            template: template_file.to_path_buf(),
+            destination: destination_file.to_path_buf(),
+            status,
+        }
+    }
+
+    /// Checks each template and destination pair, see [`Processor::check_file`].
+    pub fn check_all<P1, P2, I>(&self, files: I) -> Report
+    where
+        P1: AsRef<Path>,
+        P2: AsRef<Path>,
+        I: IntoIterator<Item = (P1, P2)>,
+    {
+        files
+            .into_iter()
+            .map(|(template_file, destination_file)| {
+                self.check_file(template_file, destination_file)
+            })
+            .collect()
+    }
+
+    /// The document generated from `template_file`, which is processed
+    /// in place when it is the same file as `destination_file`.
+    ///
+    /// The failing fences keeping their previous content take it from the current
+    /// `destination_file`, which fails when the rest of it is out of date.
+    fn generate_with(
+        &self,
+        template_file: &Path,
+        destination_file: &Path,
+        options: &TemplateOptions,
+        checking: bool,
+    ) -> Result<String> {
+        let options = &TemplateOptions {
+            on_error: options.on_error.or(checking.then_some(ErrorPolicy::Fail)),
+            ..options.clone()
+        };
+        if self.same_file(template_file, destination_file) {
+            let mut document = self.read_template(template_file)?;
+            self.process_document_in_place_with(
+                &mut document,
+                &parent_dir(template_file),
+                Some(template_file),
+                options,
+            )?;
+            return Ok(document);
+        }
+
+        // the fences keeping their previous content take it from the destination
+        let previous = self.read_destination(destination_file)?;
+        let options = &TemplateOptions {
+            previous_placeholders: previous.is_some(),
+            ..options.clone()
+        };
+        let document = self.render_with(template_file, options)?;
+        match previous {
+            Some(previous) if document.contains(PREVIOUS) => fill_previous(&document, &previous)
+                .context(format!(
+                    "cannot keep the previous content of the failing fences, \
+                     as the rest of {destination_file:?} is out of date"
+                )),
+            _ => Ok(document),
+        }
+    }
+
+    /// Whether two paths relative to the base directory are the same file
+    fn same_file(&self, a: &Path, b: &Path) -> bool {
+        self.fs.canonicalize(&self.base_dir.join(a)) == self.fs.canonicalize(&self.base_dir.join(b))
+    }
+
+    fn read_destination(&self, destination_file: &Path) -> Result<Option<String>> {
+        let dest_path = self.base_dir.join(destination_file);
+        if self.fs.is_file(&dest_path) {
+            Ok(Some(self.fs.read_to_string(&dest_path)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn output(
+        &self,
+        template_file: &Path,
+        destination_file: &Path,
+        generated_doc: &str,
+        mode: Mode,
+    ) -> Result<()> {
+        if mode == Mode::DryRun {
+            print!("{generated_doc}");
+            return Ok(());
+        }
+
+        let dest_path = self.base_dir.join(destination_file);
+        let current_doc = self.read_destination(destination_file)?;
+        let diff = Diff::lines(current_doc.as_deref().unwrap_or(""), generated_doc);
+
+        let out_of_sync = match mode {
+            Mode::Bless => current_doc.as_deref() != Some(generated_doc),
+            _ => current_doc.is_none() || diff.is_some(),
+        };
+        if !out_of_sync {
+            return Ok(());
+        }
+
+        if mode == Mode::Check {
+            let diff_str = diff.map(|d| d.to_string()).unwrap_or_default();
+            bail!(
+                "The markdown document {dest_path:?} is out of sync with {template_file:?}. 
+            Please re-run the tests and commit the updated file. 
+            This message is generated because the mode is check, which is the default on CI \
+            (set {}=update to update the file instead).\n{diff_str}",
+                Mode::ENV_VAR
+            );
+        }
+        self.fs.write(&dest_path, generated_doc)?;
+        Ok(())
+    }
+
+    /// Processes each template and destination pair. All pairs are processed
+    /// even if some of them fail, and the failures are reported together.
+    pub fn process_all<P1, P2, I>(&self, files: I) -> Result<()>
+    where
+        P1: AsRef<Path>,
+        P2: AsRef<Path>,
+        I: IntoIterator<Item = (P1, P2)>,
+    {
+        let failures = files
+            .into_iter()
+            .filter_map(|(template_file, destination_file)| {
+                self.process(&template_file, &destination_file)
+                    .err()
+                    .map(|e| format!("{:?}: {e:#}", template_file.as_ref()))
+            })
+            .collect::<Vec<_>>();
+
+        if !failures.is_empty() {
+            bail!("{}", failures.join("\n"));
+        }
+        Ok(())
+    }
+
+    /// Processes all files listed in a project configuration. The defaults of
+    /// a file take precedence over the project's, which take precedence over
+    /// the processor's. Each file is processed even if others fail, and the
+    /// result of each is returned.
+    pub fn process_project(&self, project: &ProjectConfig) -> Vec<FileResult> {
+        self.project_files(project)
+            .map(|(template, destination, options)| {
+                let result = self.process_with(&template, &destination, &options);
+                FileResult {
+                    template,
+                    destination,
+                    result,
+                }
+            })
+            .collect()
+    }
+
+    /// Checks all files listed in a project configuration, see
+    /// [`Processor::process_project`] and [`Processor::check_file`].
+    pub fn check_project(&self, project: &ProjectConfig) -> Report {
+        self.project_files(project)
+            .map(|(template, destination, options)| {
+                self.check_file_with(&template, &destination, &options)
+            })
+            .collect()
+    }
+
+    /// Generates the documents of all files listed in a project configuration,
+    /// with the options of each file, see [`Processor::process_project`]. Files
+    /// that are their own template are processed in place. Returns the template
+    /// of each file with its document.
+    pub fn render_project(&self, project: &ProjectConfig) -> Vec<(PathBuf, Result<String>)> {
+        self.project_files(project)
+            .map(|(template, destination, options)| {
+                let document = self.generate_with(&template, &destination, &options, false);
+                (template, document)
+            })
+            .collect()
+    }
+
+    /// The template and destination paths of the project's files, with their options
+    pub(crate) fn project_files<'a>(
+        &'a self,
+        project: &'a ProjectConfig,
+    ) -> impl Iterator<Item = (PathBuf, PathBuf, TemplateOptions)> + 'a {
+        let options = TemplateOptions {
+            template_root: project.root.clone(),
+            ..self
+                .options
+                .overridden_by(&project.defaults, &project.banner, project.syntax)
+        };
+        project.files.iter().map(move |file| {
+            (
+                project.root.join(&file.template),
+                project.root.join(&file.destination),
+                options.overridden_by(&file.defaults, &file.banner, file.syntax),
+            )
+        })
+    }
+}
+
+pub(crate) fn parent_dir(file: &Path) -> PathBuf {
+    file.parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|| PathBuf::from(""))
+}
@@ -0,0 +1,90 @@
+use std::{fmt, path::PathBuf};
+
+use serde::Serialize;
+
+use crate::{Diff, FenceErrors};
+
+/// How a destination file compares with the document generated from its template.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "details", rename_all = "snake_case")]
+pub enum Status {
+    UpToDate,
+    /// The destination file does not exist
+    WouldCreate,
+    WouldChange(Diff),
+    /// The document could not be generated, with an error
+    /// message for each fence that failed
+    Failed(Vec<String>),
+}
+
+impl Status {
+    pub(crate) fn failed(error: anyhow::Error) -> Self {
+        match error.downcast_ref::<FenceErrors>() {
+            Some(fence_errors) => {
+                Status::Failed(fence_errors.errors.iter().map(|e| e.to_string()).collect())
+            }
+            None => Status::Failed(vec![format!("{error:#}")]),
+        }
+    }
+}
+
+/// The [`Status`] of a template's destination file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileReport {
+    pub template: PathBuf,
+    pub destination: PathBuf,
+    pub status: Status,
+}
+
+/// The [`FileReport`]s of any number of templates, which can be
+/// collected or extended from several checks.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Report {
+    pub files: Vec<FileReport>,
+}
+
+impl Report {
+    /// Whether all destination files are up to date
+    pub fn is_up_to_date(&self) -> bool {
+        self.files.iter().all(|f| f.status == Status::UpToDate)
+    }
+
+    /// The files that are not up to date
+    pub fn outdated(&self) -> impl Iterator<Item = &FileReport> {
+        self.files.iter().filter(|f| f.status != Status::UpToDate)
+    }
+}
+
+impl FromIterator<FileReport> for Report {
+    fn from_iter<I: IntoIterator<Item = FileReport>>(iter: I) -> Self {
+        Report {
+            files: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<FileReport> for Report {
+    fn extend<I: IntoIterator<Item = FileReport>>(&mut self, iter: I) {
+        self.files.extend(iter)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for file in &self.files {
+            let destination = file.destination.display();
+            match &file.status {
+                Status::UpToDate => writeln!(f, "{destination}: up to date")?,
+                Status::WouldCreate => writeln!(f, "{destination}: would be created")?,
+                Status::WouldChange(diff) => writeln!(f, "{destination}: would change\n{diff}")?,
+                Status::Failed(errors) => {
+                    writeln!(f, "{destination}: failed")?;
+                    for error in errors {
+                        writeln!(f, "  {error}")?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
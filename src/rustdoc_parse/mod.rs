@@ -3,9 +3,9 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.
  */
 
+use crate::{Diagnostic, FileSystem};
 use anyhow::{anyhow, Context};
 use fs_err as fs;
-use std::cell::Cell;
 use std::path::Path;
 
 mod extract_doc;
@@ -17,17 +17,29 @@ pub use extract_doc::extract_doc_from_source_file;
 
 pub use self::options::RustDocOptions;
 
-pub fn parse(options: &options::RustDocOptions) -> anyhow::Result<String> {
+/// Extracts and transforms the crate level documentation of `options.source`.
+///
+/// - project_root: where to look for the cargo project, defaults to the current directory
+/// - fs: where the source and the module files visited to resolve intralinks are read from
+/// - std_fs: where the standard library sources are read from, for intralinks into it
+/// - emit_warning: called with diagnostics, such as intralinks that could not be resolved
+pub fn parse(
+    options: &options::RustDocOptions,
+    project_root: Option<&Path>,
+    fs: &dyn FileSystem,
+    std_fs: &dyn FileSystem,
+    emit_warning: impl Fn(Diagnostic),
+) -> anyhow::Result<String> {
     let project: Project = match options.workspace_project {
-        None => Project::from_current_dir()?,
-        Some(ref project) => Project::from_current_dir_workspace_project(project)?,
+        None => Project::from_dir(project_root)?,
+        Some(ref project) => Project::from_dir_workspace_project(project_root, project)?,
     };
     let entryfile: &Path = &options.source;
 
-    let doc: Doc = extract_doc_from_source_file(entryfile)?
+    let doc: Doc = extract_doc_from_source_file(fs, entryfile)?
         .ok_or_else(|| anyhow!("crate-level rustdoc not found"))?;
 
-    let doc = transform_doc(&doc, &project, entryfile, &options)?;
+    let doc = transform_doc(&doc, &project, entryfile, options, fs, std_fs, emit_warning)?;
     Ok(doc.content)
 }
 
@@ -36,6 +48,9 @@ fn transform_doc(
     project: &Project,
     entrypoint: impl AsRef<Path>,
     options: &options::RustDocOptions,
+    fs: &dyn FileSystem,
+    std_fs: &dyn FileSystem,
+    emit_warning: impl Fn(Diagnostic),
 ) -> anyhow::Result<Doc> {
     use transform::{
         DocTransform, DocTransformIntralinks, DocTransformRustMarkdownTag,
@@ -50,18 +65,16 @@ fn transform_doc(
     // TODO Use `into_ok()` once it is stable (https://github.com/rust-lang/rust/issues/61695).
     let doc = transform.transform(&doc)?;
 
-    let had_warnings = Cell::new(false);
     let transform = DocTransformIntralinks::new(
         project.get_package_name(),
         entrypoint,
-        |msg| {
-            println!("{}", msg);
-            had_warnings.set(true);
-        },
+        fs,
+        std_fs,
+        emit_warning,
         options.intralinks.clone(),
     );
 
-    Ok(transform.transform(&doc)?)
+    transform.transform(&doc)
 }
 
 #[derive(PartialEq, Eq, Debug)]
@@ -70,11 +83,10 @@ struct Project {
 }
 
 impl Project {
-    /// Creates a [`Project`] the current directory.  It will search ancestor paths until it finds
-    /// the root of the project.
-    pub fn from_current_dir() -> anyhow::Result<Project> {
-        let cmd = cargo_metadata::MetadataCommand::new();
-        let metadata = cmd.exec()?;
+    /// Creates a [`Project`] from `dir`, or the current directory if `None`.  It will search
+    /// ancestor paths until it finds the root of the project.
+    pub fn from_dir(dir: Option<&Path>) -> anyhow::Result<Project> {
+        let metadata = Project::metadata(dir)?;
         let package = metadata
             .root_package()
             .context("project has no root package")?;
@@ -82,6 +94,14 @@ impl Project {
         Ok(Project::from_package(package))
     }
 
+    fn metadata(dir: Option<&Path>) -> anyhow::Result<cargo_metadata::Metadata> {
+        let mut cmd = cargo_metadata::MetadataCommand::new();
+        if let Some(dir) = dir {
+            cmd.current_dir(dir);
+        }
+        Ok(cmd.exec()?)
+    }
+
     fn select_package<'a>(
         metadata: &'a cargo_metadata::Metadata,
         package_name: &str,
@@ -98,9 +118,11 @@ impl Project {
         }
     }
 
-    pub fn from_current_dir_workspace_project(project_name: &str) -> anyhow::Result<Project> {
-        let cmd = cargo_metadata::MetadataCommand::new();
-        let metadata = cmd.exec()?;
+    pub fn from_dir_workspace_project(
+        dir: Option<&Path>,
+        project_name: &str,
+    ) -> anyhow::Result<Project> {
+        let metadata = Project::metadata(dir)?;
 
         let package =
             Project::select_package(&metadata, project_name).context("project has no package")?;
@@ -8,6 +8,7 @@ use std::path::PathBuf;
 use serde::Deserialize;
 
 use super::transform::IntralinksConfig;
+use crate::ErrorPolicy;
 
 #[derive(PartialEq, Eq, Debug, Deserialize)]
 pub struct RustDocOptions {
@@ -16,4 +17,7 @@ pub struct RustDocOptions {
     pub workspace_project: Option<String>,
     #[serde(default)]
     pub intralinks: Option<IntralinksConfig>,
+    /// What to do when the documentation cannot be extracted
+    #[serde(default)]
+    pub on_error: Option<ErrorPolicy>,
 }
@@ -193,7 +193,7 @@ impl Display for MarkdownReferenceLink {
     }
 }
 
-pub fn markdown_link_iterator(source: &str) -> MarkdownItemIterator<MarkdownLink> {
+pub fn markdown_link_iterator(source: &str) -> MarkdownItemIterator<'_, MarkdownLink> {
     use pulldown_cmark::{Event, LinkType, Options, Parser, Tag};
 
     // We need to define a callback for broken links so that we can see broken links so that we can
@@ -288,9 +288,7 @@ fn parse_raw_reference_link_definition(
     // We need to parse things manually here, because the pulldown-cmark parser escapes the title
     // and the link.  We need the raw version to emit them later.
 
-    let Some(link_and_title) = raw_ref_def.get(label.len() + 3..).map(str::trim) else {
-        return None;
-    };
+    let link_and_title = raw_ref_def.get(label.len() + 3..).map(str::trim)?;
 
     assert_eq!(
         raw_ref_def.get(label.len() + 1..label.len() + 3),
@@ -322,7 +320,7 @@ fn parse_raw_reference_link_definition(
 
 pub fn markdown_reference_link_definition_iterator(
     source: &str,
-) -> MarkdownItemIterator<MarkdownReferenceLinkDefinition> {
+) -> MarkdownItemIterator<'_, MarkdownReferenceLinkDefinition> {
     use pulldown_cmark::{Options, Parser};
 
     let parser = Parser::new_ext(source, Options::all());
@@ -390,7 +388,7 @@ mod tests {
         assert_eq!(iter.next(), None);
 
         let source = "[another](http://foo.com)[another][one]";
-        let mut iter = markdown_link_iterator(&source).items_with_spans();
+        let mut iter = markdown_link_iterator(source).items_with_spans();
 
         let (Span { start, end }, link) = iter.next().unwrap();
         assert_eq!(
@@ -416,7 +414,7 @@ mod tests {
         assert_eq!(iter.next(), None);
 
         let source = "A [some [text]], [another [text2] (foo)](http://foo.com/foo(bar)), [another [] one][foo[]bar]";
-        let mut iter = markdown_link_iterator(&source).items_with_spans();
+        let mut iter = markdown_link_iterator(source).items_with_spans();
 
         let (Span { start, end }, link) = iter.next().unwrap();
         assert_eq!(
@@ -439,7 +437,7 @@ mod tests {
         assert_eq!(iter.next(), None);
 
         let source = "A [some \\]text], [another](http://foo.\\(com\\)), [another\\]][one\\]]";
-        let mut iter = markdown_link_iterator(&source).items_with_spans();
+        let mut iter = markdown_link_iterator(source).items_with_spans();
 
         let (Span { start, end }, link) = iter.next().unwrap();
         assert_eq!(
@@ -477,17 +475,17 @@ mod tests {
         assert_eq!(iter.next(), None);
 
         let source = "A `this is no link [link](http://foo.com)`";
-        let mut iter = markdown_link_iterator(&source).items_with_spans();
+        let mut iter = markdown_link_iterator(source).items_with_spans();
 
         assert_eq!(iter.next(), None);
 
         let source = "A\n```\nthis is no link [link](http://foo.com)\n```";
-        let mut iter = markdown_link_iterator(&source).items_with_spans();
+        let mut iter = markdown_link_iterator(source).items_with_spans();
 
         assert_eq!(iter.next(), None);
 
         let source = "A [link with `code`!](http://foo.com)!";
-        let mut iter = markdown_link_iterator(&source).items_with_spans();
+        let mut iter = markdown_link_iterator(source).items_with_spans();
 
         let (Span { start, end }, link) = iter.next().unwrap();
         assert_eq!(
@@ -514,7 +512,7 @@ mod tests {
             "#
         };
 
-        let mut iter = markdown_link_iterator(&doc).items_with_spans();
+        let mut iter = markdown_link_iterator(doc).items_with_spans();
 
         let (Span { start, end }, link) = iter.next().unwrap();
         assert_eq!(
@@ -548,7 +546,7 @@ mod tests {
         };
         let source = doc;
 
-        let mut iter = markdown_reference_link_definition_iterator(&doc).items_with_spans();
+        let mut iter = markdown_reference_link_definition_iterator(doc).items_with_spans();
 
         let (Span { start, end }, link) = iter.next().unwrap();
         assert_eq!(
@@ -8,6 +8,7 @@ use crate::rustdoc_parse::transform::intralinks::links::{
 };
 use crate::rustdoc_parse::transform::DocTransform;
 use crate::rustdoc_parse::Doc;
+use crate::{Diagnostic, FileSystem};
 use anyhow::{anyhow, bail, Context};
 use fs_err as fs;
 use module_walker::walk_module_file;
@@ -52,35 +53,42 @@ pub struct IntralinksConfig {
     pub strip_links: Option<bool>,
 }
 
-pub struct DocTransformIntralinks<F> {
+pub struct DocTransformIntralinks<'a, F> {
     crate_name: String,
     entrypoint: PathBuf,
+    fs: &'a dyn FileSystem,
+    /// Where the standard library sources are read from
+    std_fs: &'a dyn FileSystem,
     emit_warning: F,
     config: IntralinksConfig,
 }
 
-impl<F> DocTransformIntralinks<F>
+impl<'a, F> DocTransformIntralinks<'a, F>
 where
-    F: Fn(&str),
+    F: Fn(Diagnostic),
 {
     pub fn new(
         crate_name: impl Into<String>,
         entrypoint: impl AsRef<Path>,
+        fs: &'a dyn FileSystem,
+        std_fs: &'a dyn FileSystem,
         emit_warning: F,
         config: Option<IntralinksConfig>,
-    ) -> DocTransformIntralinks<F> {
+    ) -> DocTransformIntralinks<'a, F> {
         DocTransformIntralinks {
             crate_name: crate_name.into(),
             entrypoint: entrypoint.as_ref().to_path_buf(),
+            fs,
+            std_fs,
             emit_warning,
             config: config.unwrap_or_default(),
         }
     }
 }
 
-impl<F> DocTransform for DocTransformIntralinks<F>
+impl<F> DocTransform for DocTransformIntralinks<'_, F>
 where
-    F: Fn(&str),
+    F: Fn(Diagnostic),
 {
     type E = anyhow::Error;
 
@@ -94,7 +102,13 @@ where
 
         // We only load symbols type information when we need them.
         let symbols_type = match self.config.strip_links.unwrap_or(false) {
-            false => load_symbols_type(&self.entrypoint, &symbols, &self.emit_warning)?,
+            false => load_symbols_type(
+                self.fs,
+                self.std_fs,
+                &self.entrypoint,
+                &symbols,
+                &self.emit_warning,
+            )?,
             true => HashMap::new(),
         };
 
@@ -114,7 +128,7 @@ fn rewrite_links(
     doc: &Doc,
     symbols_type: &HashMap<ItemPath, SymbolType>,
     crate_name: &str,
-    emit_warning: &impl Fn(&str),
+    emit_warning: &impl Fn(Diagnostic),
     config: &IntralinksConfig,
 ) -> Doc {
     let RewriteReferenceLinksResult {
@@ -477,12 +491,13 @@ fn check_explore_module(
 }
 
 fn explore_crate<P: AsRef<Path>>(
+    fs: &dyn FileSystem,
     file: P,
     crate_symbol: &ItemPath,
     symbols: &HashSet<ItemPath>,
     paths_to_explore: &HashSet<ItemPath>,
     symbols_type: &mut HashMap<ItemPath, SymbolType>,
-    emit_warning: &impl Fn(&str),
+    emit_warning: &impl Fn(Diagnostic),
 ) -> anyhow::Result<()> {
     let mut modules_visited: HashSet<ItemPath> = HashSet::new();
 
@@ -510,6 +525,7 @@ fn explore_crate<P: AsRef<Path>>(
     };
 
     walk_module_file(
+        fs,
         file,
         crate_symbol,
         &mut visit,
@@ -519,9 +535,11 @@ fn explore_crate<P: AsRef<Path>>(
 }
 
 fn load_symbols_type<P: AsRef<Path>>(
+    fs: &dyn FileSystem,
+    std_fs: &dyn FileSystem,
     entry_point: P,
     symbols: &HashSet<ItemPath>,
-    emit_warning: &impl Fn(&str),
+    emit_warning: &impl Fn(Diagnostic),
 ) -> anyhow::Result<HashMap<ItemPath, SymbolType>> {
     let paths_to_explore: HashSet<ItemPath> = all_ancestor_paths(symbols.iter());
     let mut symbols_type: HashMap<ItemPath, SymbolType> = HashMap::new();
@@ -534,6 +552,7 @@ fn load_symbols_type<P: AsRef<Path>>(
 
     for Crate { name, entrypoint } in std_lib_crates {
         explore_crate(
+            std_fs,
             entrypoint,
             &ItemPath::root(&name),
             symbols,
@@ -544,6 +563,7 @@ fn load_symbols_type<P: AsRef<Path>>(
     }
 
     explore_crate(
+        fs,
         entry_point,
         &ItemPath::new(ItemPathAnchor::Crate),
         symbols,
@@ -676,7 +696,7 @@ fn documentation_url(
         }
     }
 
-    return Some(format!("{}{}", link, fragment.unwrap_or("")));
+    Some(format!("{}{}", link, fragment.unwrap_or("")))
 }
 
 enum MarkdownLinkAction {
@@ -689,7 +709,7 @@ fn markdown_link(
     link: &Link,
     symbols_type: &HashMap<ItemPath, SymbolType>,
     crate_name: &str,
-    emit_warning: &impl Fn(&str),
+    emit_warning: &impl Fn(Diagnostic),
     config: &IntralinksConfig,
 ) -> MarkdownLinkAction {
     match link.link_as_item_path() {
@@ -699,13 +719,19 @@ fn markdown_link(
                 symbols_type,
                 crate_name,
                 link.link_fragment(),
-                &config,
+                config,
             );
 
             match link {
                 Some(l) => MarkdownLinkAction::Link(l.into()),
                 None => {
-                    emit_warning(&format!("Could not resolve definition of `{}`.", symbol));
+                    emit_warning(
+                        Diagnostic::warning(format!(
+                            "Could not resolve definition of `{}`.",
+                            symbol
+                        ))
+                        .with_symbol(symbol.to_string()),
+                    );
 
                     // This was an intralink, but we were not able to generate a link.
                     MarkdownLinkAction::Strip
@@ -720,7 +746,7 @@ fn rewrite_markdown_links(
     doc: &Doc,
     symbols_type: &HashMap<ItemPath, SymbolType>,
     crate_name: &str,
-    emit_warning: &impl Fn(&str),
+    emit_warning: &impl Fn(Diagnostic),
     config: &IntralinksConfig,
     reference_links_to_remove: &HashSet<UniCase<String>>,
 ) -> Doc {
@@ -782,7 +808,7 @@ fn rewrite_reference_links_definitions(
     doc: &Doc,
     symbols_type: &HashMap<ItemPath, SymbolType>,
     crate_name: &str,
-    emit_warning: &impl Fn(&str),
+    emit_warning: &impl Fn(Diagnostic),
     config: &IntralinksConfig,
 ) -> RewriteReferenceLinksResult {
     use crate::rustdoc_parse::utils::ItemOrOther;
@@ -916,6 +942,7 @@ fn get_standard_libraries() -> anyhow::Result<Vec<Crate>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::DiskFileSystem;
     use indoc::indoc;
     use module_walker::walk_module_items;
     use std::cell::RefCell;
@@ -994,7 +1021,7 @@ mod tests {
         crate_symbol: &ItemPath,
         should_explore_module: impl Fn(&ItemPath) -> bool,
         symbols_type: &mut HashMap<ItemPath, SymbolType>,
-        emit_warning: impl Fn(&str),
+        emit_warning: impl Fn(Diagnostic),
     ) {
         let mut modules_visited: HashSet<ItemPath> = HashSet::new();
 
@@ -1014,6 +1041,7 @@ mod tests {
         };
 
         walk_module_items(
+            &DiskFileSystem,
             ast,
             dir,
             crate_symbol,
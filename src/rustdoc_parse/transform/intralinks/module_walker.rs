@@ -3,30 +3,30 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.
  */
 
-use fs_err as fs;
 use std::path::{Path, PathBuf};
 use syn::Ident;
 use syn::Item;
 
 use super::ItemPath;
+use crate::{Diagnostic, FileSystem};
 
-fn file_ast<P: AsRef<Path>>(filepath: P) -> anyhow::Result<syn::File> {
-    let src = fs::read_to_string(filepath)?;
+fn file_ast<P: AsRef<Path>>(fs: &dyn FileSystem, filepath: P) -> anyhow::Result<syn::File> {
+    let src = fs.read_to_string(filepath.as_ref())?;
 
     Ok(syn::parse_file(&src)?)
 }
 
 /// Determines the module filename, which can be `<module>.rs` or `<module>/mod.rs`.
-fn module_filename(dir: &Path, module: &Ident) -> Option<PathBuf> {
+fn module_filename(fs: &dyn FileSystem, dir: &Path, module: &Ident) -> Option<PathBuf> {
     let mod_file = dir.join(format!("{}.rs", module));
 
-    if mod_file.is_file() {
+    if fs.is_file(&mod_file) {
         return Some(mod_file);
     }
 
     let mod_file = dir.join(module.to_string()).join("mod.rs");
 
-    if mod_file.is_file() {
+    if fs.is_file(&mod_file) {
         return Some(mod_file);
     }
 
@@ -34,12 +34,13 @@ fn module_filename(dir: &Path, module: &Ident) -> Option<PathBuf> {
 }
 
 pub(super) fn walk_module_items(
+    fs: &dyn FileSystem,
     ast: &[Item],
     dir: &Path,
     mod_symbol: &ItemPath,
     visit: &mut impl FnMut(&ItemPath, &Item),
     explore_module: &mut impl FnMut(&ItemPath, &syn::ItemMod) -> bool,
-    emit_warning: &impl Fn(&str),
+    emit_warning: &impl Fn(Diagnostic),
 ) -> anyhow::Result<()> {
     for item in ast.iter() {
         visit(mod_symbol, item);
@@ -51,6 +52,7 @@ pub(super) fn walk_module_items(
                 match &module.content {
                     Some((_, items)) => {
                         walk_module_items(
+                            fs,
                             items,
                             dir,
                             &child_module_symbol,
@@ -59,13 +61,17 @@ pub(super) fn walk_module_items(
                             emit_warning,
                         )?;
                     }
-                    None => match module_filename(dir, &module.ident) {
-                        None => emit_warning(&format!(
-                            "Unable to find module file for module {} in directory \"{}\"",
-                            child_module_symbol,
-                            dir.display()
-                        )),
+                    None => match module_filename(fs, dir, &module.ident) {
+                        None => emit_warning(
+                            Diagnostic::warning(format!(
+                                "Unable to find module file for module {} in directory \"{}\"",
+                                child_module_symbol,
+                                dir.display()
+                            ))
+                            .with_symbol(child_module_symbol.to_string()),
+                        ),
                         Some(mod_filename) => walk_module_file(
+                            fs,
                             mod_filename,
                             &child_module_symbol,
                             visit,
@@ -82,19 +88,21 @@ pub(super) fn walk_module_items(
 }
 
 pub(super) fn walk_module_file<P: AsRef<Path>>(
+    fs: &dyn FileSystem,
     file: P,
     mod_symbol: &ItemPath,
     visit: &mut impl FnMut(&ItemPath, &Item),
     explore_module: &mut impl FnMut(&ItemPath, &syn::ItemMod) -> bool,
-    emit_warning: &impl Fn(&str),
+    emit_warning: &impl Fn(Diagnostic),
 ) -> anyhow::Result<()> {
     let dir: &Path = file
         .as_ref()
         .parent()
         .unwrap_or_else(|| panic!("failed to get directory of \"{}\"", file.as_ref().display()));
-    let ast: syn::File = file_ast(&file)?;
+    let ast: syn::File = file_ast(fs, &file)?;
 
     walk_module_items(
+        fs,
         &ast.items,
         dir,
         mod_symbol,
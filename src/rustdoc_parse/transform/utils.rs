@@ -15,10 +15,10 @@ pub fn is_rust_code_block(tags: &str) -> bool {
     })
 }
 
-pub fn rust_code_block_iterator(source: &str) -> MarkdownItemIterator<&str> {
+pub fn rust_code_block_iterator(source: &str) -> MarkdownItemIterator<'_, &str> {
     use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag};
 
-    let parser = Parser::new_ext(&source, Options::all());
+    let parser = Parser::new_ext(source, Options::all());
 
     let iter = parser
         .into_offset_iter()
@@ -74,7 +74,7 @@ mod tests {
             "#
         };
 
-        let mut iter = rust_code_block_iterator(&doc).items();
+        let mut iter = rust_code_block_iterator(doc).items();
 
         assert_eq!(iter.next(), Some("```\nprintln!(\"first\");\n```"));
         assert_eq!(iter.next(), Some("```rust\nprintln!(\"second\");\n```"));
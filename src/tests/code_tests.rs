@@ -0,0 +1,103 @@
+use insta::assert_snapshot;
+
+use crate::{Banner, MemoryFileSystem, Mode, Processor};
+
+const EXAMPLE: &str = r#"use markdown_includes::Processor;
+
+fn main() {
+    // ANCHOR: setup
+    let processor = Processor::new()
+        .strict(true);
+    // ANCHOR_END: setup
+    processor.process("README.tpl.md", "README.md").unwrap();
+}
+"#;
+
+/// Renders `template` as _docs/README.tpl.md_, next to an _examples/basic.rs_
+/// and a _config/default.toml_
+fn render(template: &str) -> String {
+    let files = MemoryFileSystem::new()
+        .with_file("docs/README.tpl.md", template)
+        .with_file("examples/basic.rs", EXAMPLE)
+        .with_file("config/default.toml", "[server]\nport = 8080\n");
+    Processor::new()
+        .file_system(files.clone())
+        .banner(Banner::None)
+        .mode(Mode::Update)
+        .process("docs/README.tpl.md", "docs/README.md")
+        .unwrap();
+    files.get("docs/README.md").unwrap()
+}
+
+#[test]
+fn test_code() {
+    let output = render("```toml code\nsource = \"../config/default.toml\"\n```\n");
+
+    assert_snapshot!(output, @r###"
+    ```toml
+    [server]
+    port = 8080
+    ```
+    "###);
+}
+
+#[test]
+fn test_code_whole_file_without_anchors() {
+    let output = render("```toml code\nsource = \"../examples/basic.rs\"\n```\n");
+
+    assert_snapshot!(output, @r###"
+    ```rust
+    use markdown_includes::Processor;
+
+    fn main() {
+        let processor = Processor::new()
+            .strict(true);
+        processor.process("README.tpl.md", "README.md").unwrap();
+    }
+    ```
+    "###);
+}
+
+#[test]
+fn test_code_lines() {
+    let output = render(
+        "```toml code\nsource = \"../examples/basic.rs\"\nlines = \"5-6\"\nlanguage = \"rust,ignore\"\n```\n",
+    );
+
+    assert_snapshot!(output, @r###"
+    ```rust,ignore
+    let processor = Processor::new()
+        .strict(true);
+    ```
+    "###);
+}
+
+#[test]
+fn test_code_anchor() {
+    let output =
+        render("```toml code\nsource = \"../examples/basic.rs\"\nanchor = \"setup\"\n```\n");
+
+    assert_snapshot!(output, @r###"
+    ```rust
+    let processor = Processor::new()
+        .strict(true);
+    ```
+    "###);
+}
+
+#[test]
+fn test_code_errors() {
+    let output = render(
+        "```toml code\nsource = \"../examples/basic.rs\"\nanchor = \"teardown\"\n```\n\n```toml code\nsource = \"../examples/basic.rs\"\nlines = \"8-20\"\n```\n",
+    );
+
+    assert_snapshot!(output, @r###"
+    ```text
+    code: in "docs/../examples/basic.rs": no `ANCHOR: teardown` marker
+    ```
+
+    ```text
+    code: in "docs/../examples/basic.rs": the lines `8-20` are not within the 9 lines of the file
+    ```
+    "###);
+}
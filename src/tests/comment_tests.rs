@@ -0,0 +1,150 @@
+use std::path::Path;
+
+use insta::assert_snapshot;
+
+use crate::{Banner, MemoryFileSystem, Mode, Processor, ProjectConfig, Syntax};
+
+const DOC: &str = r###"
+# Title
+
+<!-- includes:toc -->
+
+## Usage
+
+<!-- includes:toc
+header = "**Contents**"
+-->
+
+```markdown
+<!-- includes:toc -->
+```
+
+```toml toc
+```
+"###;
+
+#[test]
+fn test_comment_directives() {
+    let mut document = DOC.trim().to_string();
+    Processor::new()
+        .process_document(&mut document, Path::new(""))
+        .unwrap();
+
+    assert_snapshot!(document, @r###"
+    # Title
+
+    - [Title](#title)
+        - [Usage](#usage)
+
+    ## Usage
+
+    **Contents**
+
+    - [Title](#title)
+        - [Usage](#usage)
+
+    ```markdown
+    <!-- includes:toc -->
+    ```
+
+    - [Title](#title)
+        - [Usage](#usage)
+    "###);
+}
+
+#[test]
+fn test_syntax() {
+    let mut document = DOC.trim().to_string();
+    Processor::new()
+        .syntax(Syntax::Fences)
+        .process_document(&mut document, Path::new(""))
+        .unwrap();
+    assert!(document.starts_with("# Title\n\n<!-- includes:toc -->\n"));
+    assert!(!document.contains("```toml toc"));
+
+    let mut document = DOC.trim().to_string();
+    Processor::new()
+        .syntax(Syntax::Comments)
+        .process_document(&mut document, Path::new(""))
+        .unwrap();
+    assert!(!document.contains("<!-- includes:toc -->\n\n## Usage"));
+    assert!(document.ends_with("```toml toc\n```"));
+}
+
+#[test]
+fn test_syntax_per_template() {
+    let template = "# Title\n\n<!-- includes:toc -->\n\n```toml toc\n```\n";
+    let files = MemoryFileSystem::new()
+        .with_file("fences.tpl.md", template)
+        .with_file("comments.tpl.md", template);
+    let project: ProjectConfig = r###"
+syntax = "fences"
+
+[[files]]
+template = "fences.tpl.md"
+destination = "fences.md"
+
+[[files]]
+template = "comments.tpl.md"
+destination = "comments.md"
+syntax = "comments"
+"###
+    .parse()
+    .unwrap();
+
+    let results = Processor::new()
+        .file_system(files.clone())
+        .banner(Banner::None)
+        .mode(Mode::Update)
+        .process_project(&project);
+    assert!(results.iter().all(|r| r.result.is_ok()));
+
+    assert_snapshot!(files.get("fences.md").unwrap(), @r###"
+    # Title
+
+    <!-- includes:toc -->
+
+    - [Title](#title)
+    "###);
+    assert_snapshot!(files.get("comments.md").unwrap(), @r###"
+    # Title
+
+    - [Title](#title)
+
+    ```toml toc
+    ```
+    "###);
+}
+
+#[test]
+fn test_unclosed_directive() {
+    let mut document = "# Title\n\n<!-- includes:toc\n".to_string();
+    let error = Processor::new()
+        .process_document(&mut document, Path::new(""))
+        .unwrap_err();
+    assert_snapshot!(error, @r###"
+    fence `toc`: unclosed comment
+     --> 3:1
+      |
+    3 | <!-- includes:toc
+      | ^^^^^^^^^^^^^^^^^
+    "###);
+}
+
+#[test]
+fn test_end_marker_in_template() {
+    let mut document =
+        "# Title\n\n<!-- includes:toc -->\n- [Outdated](#outdated)\n<!-- /includes:toc -->\n"
+            .to_string();
+    let error = Processor::new()
+        .process_document(&mut document, Path::new(""))
+        .unwrap_err();
+
+    assert_snapshot!(error, @r###"
+    fence `toc`: end marker of an in-place region in a template, in-place regions are only updated in a file that is its own destination
+     --> 5:1
+      |
+    5 | <!-- /includes:toc -->
+      | ^^^^^^^^^^^^^^^^^^^^^^
+    "###);
+}
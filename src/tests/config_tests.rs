@@ -0,0 +1,340 @@
+use fs_err as fs;
+use insta::assert_snapshot;
+
+use super::temp_dir;
+use crate::{Banner, MemoryFileSystem, Mode, Processor, ProjectConfig};
+
+const TEMPLATE: &str = r###"# Title
+
+```toml toc
+```
+
+## Section
+"###;
+
+#[test]
+fn test_process_project() {
+    let dir = temp_dir("process_project");
+    fs::create_dir_all(dir.join("docs")).unwrap();
+    fs::write(dir.join("README.tpl.md"), TEMPLATE).unwrap();
+    fs::write(dir.join("docs/guide.tpl.md"), TEMPLATE).unwrap();
+    fs::write(
+        dir.join(ProjectConfig::FILE_NAME),
+        r###"
+[defaults.toc]
+bullet = "*"
+header = "Contents"
+
+[[files]]
+template = "README.tpl.md"
+destination = "README.md"
+
+[[files]]
+template = "docs/guide.tpl.md"
+destination = "docs/guide.md"
+defaults.toc.bullet = "+"
+
+[[files]]
+template = "missing.tpl.md"
+destination = "missing.md"
+"###,
+    )
+    .unwrap();
+
+    let project = ProjectConfig::load(&dir).unwrap();
+    let results = Processor::new()
+        .mode(Mode::Update)
+        .banner(Banner::None)
+        .process_project(&project);
+
+    let outcomes = results
+        .iter()
+        .map(|r| (r.destination.strip_prefix(&dir).unwrap(), r.result.is_ok()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        outcomes,
+        vec![
+            ("README.md".as_ref(), true),
+            ("docs/guide.md".as_ref(), true),
+            ("missing.md".as_ref(), false)
+        ]
+    );
+
+    assert_snapshot!(fs::read_to_string(dir.join("README.md")).unwrap(), @r###"
+    # Title
+
+    Contents
+
+    * [Title](#title)
+        * [Section](#section)
+
+    ## Section
+    "###);
+    assert_snapshot!(fs::read_to_string(dir.join("docs/guide.md")).unwrap(), @r###"
+    # Title
+
+    Contents
+
+    + [Title](#title)
+        + [Section](#section)
+
+    ## Section
+    "###);
+}
+
+#[test]
+fn test_render_project() {
+    let files = MemoryFileSystem::new()
+        .with_file("README.tpl.md", TEMPLATE)
+        .with_file(
+            "guide.md",
+            "# Guide\n\n<!-- includes:toc -->\n<!-- /includes:toc -->\n",
+        );
+    let project: ProjectConfig = r###"
+banner.enabled = false
+
+[[files]]
+template = "README.tpl.md"
+destination = "README.md"
+defaults.toc.bullet = "+"
+
+[[files]]
+template = "guide.md"
+destination = "guide.md"
+"###
+    .parse()
+    .unwrap();
+
+    let rendered = Processor::new()
+        .file_system(files.clone())
+        .render_project(&project)
+        .into_iter()
+        .map(|(template, document)| format!("{}:\n{}", template.display(), document.unwrap()))
+        .collect::<String>();
+    assert_snapshot!(rendered, @r###"
+    README.tpl.md:
+    # Title
+
+    + [Title](#title)
+        + [Section](#section)
+
+    ## Section
+    guide.md:
+    # Guide
+
+    <!-- includes:toc -->
+
+    - [Guide](#guide)
+
+    <!-- /includes:toc -->
+    "###);
+    assert!(files.get("README.md").is_none());
+}
+
+#[test]
+fn test_load_from_cargo_metadata() {
+    let dir = temp_dir("load_from_cargo_metadata");
+    fs::write(
+        dir.join("Cargo.toml"),
+        r###"
+[workspace]
+members = ["a"]
+
+[workspace.metadata.markdown-includes]
+defaults.rustdoc.intralinks.strip_links = true
+files = [{ template = "a/README.tpl.md", destination = "a/README.md" }]
+"###,
+    )
+    .unwrap();
+
+    let project = ProjectConfig::load(&dir).unwrap();
+    assert_eq!(project.root, dir);
+    assert_eq!(project.files.len(), 1);
+    assert_eq!(project.files[0].destination.to_str(), Some("a/README.md"));
+    assert_eq!(
+        project.defaults.to_string().trim(),
+        "[rustdoc.intralinks]\nstrip_links = true"
+    );
+}
+
+#[test]
+fn test_from_file() {
+    let dir = temp_dir("config_from_file");
+    fs::create_dir_all(dir.join("docs")).unwrap();
+    fs::write(
+        dir.join("docs/includes.toml"),
+        "[[files]]\ntemplate = \"guide.tpl.md\"\ndestination = \"guide.md\"\n",
+    )
+    .unwrap();
+    fs::write(dir.join("Cargo.toml"), "[package]\nname = \"a\"\n").unwrap();
+
+    let project = ProjectConfig::from_file(dir.join("docs/includes.toml")).unwrap();
+    assert_eq!(project.root, dir.join("docs"));
+    assert_eq!(project.files[0].template.to_str(), Some("guide.tpl.md"));
+
+    let err = ProjectConfig::from_file(dir.join("Cargo.toml")).unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("no markdown-includes metadata found in"));
+}
+
+#[test]
+fn test_missing_config() {
+    let dir = temp_dir("missing_config");
+    assert!(ProjectConfig::load(&dir).is_err());
+}
+
+#[test]
+fn test_unknown_config_key() {
+    let error = "[[file]]\ntemplate = \"README.tpl.md\"\ndestination = \"README.md\"\n"
+        .parse::<ProjectConfig>()
+        .unwrap_err();
+    assert_snapshot!(error, @r###"
+    TOML parse error at line 1, column 3
+      |
+    1 | [[file]]
+      |   ^^^^
+    unknown field `file`, expected one of `defaults`, `banner`, `syntax`, `files`
+    "###);
+
+    let error = "[[files]]\ntemplate = \"README.tpl.md\"\ndestinaton = \"README.md\"\n"
+        .parse::<ProjectConfig>()
+        .unwrap_err();
+    assert_snapshot!(error, @r###"
+    TOML parse error at line 3, column 1
+      |
+    3 | destinaton = "README.md"
+      | ^^^^^^^^^^
+    unknown field `destinaton`, expected one of `template`, `destination`, `defaults`, `banner`, `syntax`
+    "###);
+}
+
+#[test]
+fn test_project_banner() {
+    let dir = temp_dir("project_banner");
+    fs::write(
+        dir.join("README.tpl.md"),
+        "+++\ntitle = \"Readme\"\n+++\n# Title\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join(ProjectConfig::FILE_NAME),
+        r###"
+[banner]
+text = "<!-- {template}: run `{command}` -->"
+position = "after-front-matter"
+command = "cargo xtask docs"
+
+[[files]]
+template = "README.tpl.md"
+destination = "README.md"
+
+[[files]]
+template = "README.tpl.md"
+destination = "plain.md"
+banner.enabled = false
+"###,
+    )
+    .unwrap();
+
+    let project = ProjectConfig::load(&dir).unwrap();
+    let results = Processor::new()
+        .mode(Mode::Update)
+        .process_project(&project);
+    assert!(results.iter().all(|r| r.result.is_ok()));
+
+    assert_snapshot!(fs::read_to_string(dir.join("README.md")).unwrap(), @r###"
+    +++
+    title = "Readme"
+    +++
+    <!-- README.tpl.md: run `cargo xtask docs` -->
+    # Title
+    "###);
+    assert_snapshot!(fs::read_to_string(dir.join("plain.md")).unwrap(), @r###"
+    +++
+    title = "Readme"
+    +++
+    # Title
+    "###);
+}
+
+#[test]
+fn test_from_workspace_manifest() {
+    let from_manifest = ProjectConfig::from_workspace("Cargo.toml").unwrap();
+    let from_dir = ProjectConfig::from_workspace(".").unwrap();
+    assert_eq!(from_manifest, from_dir);
+    assert!(!from_manifest.files.is_empty());
+}
+
+#[test]
+fn test_from_workspace() {
+    let dir = temp_dir("from_workspace");
+    fs::write(
+        dir.join("Cargo.toml"),
+        "[workspace]\nmembers = [\"a\", \"b\", \"c\"]\n",
+    )
+    .unwrap();
+    for member in ["a", "b", "c"] {
+        fs::create_dir_all(dir.join(member).join("src")).unwrap();
+        fs::write(
+            dir.join(member).join("Cargo.toml"),
+            format!(
+                "[package]\nname = \"{member}\"\nversion = \"0.1.0\"\nreadme = \"README.md\"\n"
+            ),
+        )
+        .unwrap();
+        fs::write(
+            dir.join(member).join("src/lib.rs"),
+            format!("//! Crate {member}\n"),
+        )
+        .unwrap();
+    }
+    fs::write(
+        dir.join("a/src/README.tpl.md"),
+        "```toml rustdoc\nsource = \"lib.rs\"\n```\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("b/README.md"),
+        "# B\n<!-- includes:rustdoc source = \"src/lib.rs\" -->\n<!-- /includes:rustdoc -->\n",
+    )
+    .unwrap();
+
+    let project = ProjectConfig::from_workspace(&dir).unwrap();
+    let files = project
+        .files
+        .iter()
+        .map(|f| {
+            (
+                f.template.to_str().unwrap(),
+                f.destination.to_str().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        files,
+        [
+            ("a/src/README.tpl.md", "a/README.md"),
+            ("b/README.md", "b/README.md")
+        ]
+    );
+
+    let results = Processor::new()
+        .project_root(&dir)
+        .banner(Banner::None)
+        .mode(Mode::Update)
+        .process_project(&project);
+    assert!(results.iter().all(|r| r.result.is_ok()), "{results:?}");
+    assert_eq!(
+        fs::read_to_string(dir.join("a/README.md")).unwrap(),
+        "Crate a\n"
+    );
+    assert_snapshot!(fs::read_to_string(dir.join("b/README.md")).unwrap(), @r###"
+    # B
+    <!-- includes:rustdoc source = "src/lib.rs" -->
+
+    Crate b
+
+    <!-- /includes:rustdoc -->
+    "###);
+}
@@ -0,0 +1,72 @@
+use std::{cell::RefCell, path::Path};
+
+use fs_err as fs;
+use insta::assert_snapshot;
+
+use super::temp_dir;
+use crate::{fence::RecordedDisk, Banner, Dependencies, FileSystem, Mode, Processor};
+
+#[test]
+fn test_dependencies() {
+    let dir = temp_dir("dependencies");
+    fs::create_dir_all(dir.join("src/inner")).unwrap();
+    fs::write(
+        dir.join("README.tpl.md"),
+        "```toml rustdoc\nsource = \"src/lib.rs\"\n```\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("src/lib.rs"),
+        "//! Uses [`Item`](crate::inner::Item).\n//!\n//! ```toml rustdoc\n//! source = \"other.rs\"\n//! ```\n\nmod inner;\n",
+    )
+    .unwrap();
+    fs::write(dir.join("src/inner/mod.rs"), "pub struct Item;\n").unwrap();
+    fs::write(dir.join("src/other.rs"), "//! Other docs\n").unwrap();
+    fs::write(dir.join("src/unused.rs"), "//! Not included\n").unwrap();
+
+    let processor = Processor::new()
+        .base_dir(&dir)
+        .banner(Banner::None)
+        .mode(Mode::Update);
+    processor.process("README.tpl.md", "README.md").unwrap();
+
+    let dependencies = processor.dependencies();
+    let paths = dependencies
+        .paths()
+        .map(|p| p.strip_prefix(&dir).unwrap().to_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        [
+            "README.tpl.md",
+            "src/inner/mod.rs",
+            "src/lib.rs",
+            "src/other.rs"
+        ]
+    );
+
+    let lines = dependencies
+        .rerun_if_changed()
+        .replace(dir.to_str().unwrap(), "");
+    assert_snapshot!(lines, @r###"
+    cargo:rerun-if-changed=/README.tpl.md
+    cargo:rerun-if-changed=/src/inner/mod.rs
+    cargo:rerun-if-changed=/src/lib.rs
+    cargo:rerun-if-changed=/src/other.rs
+    "###);
+}
+
+#[test]
+fn test_recorded_disk() {
+    let dependencies = RefCell::new(Dependencies::default());
+    let disk = RecordedDisk {
+        dependencies: &dependencies,
+    };
+    assert!(disk.read_to_string(Path::new("Cargo.toml")).is_ok());
+    assert!(disk.is_file(Path::new("src/lib.rs")));
+
+    assert_eq!(
+        dependencies.borrow().paths().collect::<Vec<_>>(),
+        [Path::new("Cargo.toml")]
+    );
+}
@@ -0,0 +1,105 @@
+use fs_err as fs;
+use indoc::indoc;
+use insta::assert_snapshot;
+
+use super::temp_dir;
+use crate::{Diff, Processor};
+
+#[test]
+fn test_trailing_lines() {
+    let diff = Diff::lines("a\nb\n", "a\nb\nc\nd\n").unwrap();
+    assert_snapshot!(diff, @r###"
+    @@ -1,2 +1,4 @@
+     a
+     b
+    +c
+    +d
+    "###);
+
+    let diff = Diff::lines("a\nb\nc\n", "a\n").unwrap();
+    assert_snapshot!(diff, @r###"
+    @@ -1,3 +1,1 @@
+     a
+    -b
+    -c
+    "###);
+}
+
+#[test]
+fn test_in_sync() {
+    assert_eq!(Diff::lines("a \nb\n", "a\nb"), None);
+    assert_eq!(Diff::lines("", ""), None);
+}
+
+#[test]
+fn test_hunks() {
+    let old = indoc! {"
+        1
+        2
+        3
+        4
+        5
+        6
+        7
+        8
+        9
+        10
+        11
+        12
+    "};
+    let new = indoc! {"
+        1
+        two
+        3
+        4
+        5
+        6
+        7
+        8
+        9
+        10
+        12
+    "};
+    let diff = Diff::lines(old, new).unwrap();
+    assert_eq!(diff.hunks.len(), 2);
+    assert_snapshot!(diff, @r###"
+    @@ -1,5 +1,5 @@
+     1
+    -2
+    +two
+     3
+     4
+     5
+    @@ -8,5 +8,4 @@
+     8
+     9
+     10
+    -11
+     12
+    "###);
+}
+
+#[test]
+fn test_check() {
+    let dir = temp_dir("check");
+    fs::write(dir.join("README.tpl.md"), "# Title\n\nText\n").unwrap();
+    let processor = Processor::new().base_dir(&dir);
+
+    let diff = processor.check("README.tpl.md", "README.md").unwrap();
+    assert_snapshot!(diff.unwrap(), @r###"
+    @@ -0,0 +1,6 @@
+    +<!--
+    +Please don't edit. This document has been generated from "README.tpl.md"
+    +-->
+    +# Title
+    +
+    +Text
+    "###);
+
+    fs::write(
+        dir.join("README.md"),
+        processor.render("README.tpl.md").unwrap(),
+    )
+    .unwrap();
+    assert_eq!(processor.check("README.tpl.md", "README.md").unwrap(), None);
+}
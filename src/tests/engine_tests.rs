@@ -0,0 +1,175 @@
+use std::path::Path;
+
+use insta::assert_snapshot;
+
+use crate::process_includes_document;
+
+#[test]
+fn test_multiple_fences() {
+    let doc = r###"
+# Title
+
+```toml toc
+header = "## Contents"
+```
+
+```toml rustdoc
+source = "src/tests/rustdoc1.rs"
+```
+
+Between the fences
+
+```toml rustdoc
+source = "src/tests/rustdoc2.rs"
+```
+
+# Last heading
+"###;
+
+    let mut document = doc.trim().to_string();
+    process_includes_document(&mut document, Path::new("")).unwrap();
+
+    assert_snapshot!(document, @r###"
+    # Title
+
+    ## Contents
+
+    - [Title](#title)
+    - [Second crate](#second-crate)
+        - [Usage](#usage)
+    - [Last heading](#last-heading)
+
+    Some rust doc here
+    ```rust
+    let s = String::new();
+    ```
+
+    Howdy
+
+    Between the fences
+
+    # Second crate
+
+    A longer piece of rust doc, which makes sure that
+    the fences after it are moved accordingly.
+
+    ## Usage
+
+    Call it.
+
+    # Last heading
+    "###);
+}
+
+#[test]
+fn test_only_top_level_fences() {
+    let doc = r###"
+# Title
+
+````markdown
+An example of a fence, which is left as is:
+
+```toml toc
+```
+````
+
+~~~toml toc
+header = "## Contents"
+~~~
+
+- a list item with a fence, which is left as is:
+
+  ```toml toc
+  ```
+
+> ```toml toc
+> ```
+
+```toml toc
+bullet = "*"
+```
+"###;
+
+    let mut document = doc.trim().to_string();
+    process_includes_document(&mut document, Path::new("")).unwrap();
+
+    assert_snapshot!(document, @r###"
+    # Title
+
+    ````markdown
+    An example of a fence, which is left as is:
+
+    ```toml toc
+    ```
+    ````
+
+    ## Contents
+
+    - [Title](#title)
+
+    - a list item with a fence, which is left as is:
+
+      ```toml toc
+      ```
+
+    > ```toml toc
+    > ```
+
+    * [Title](#title)
+        * [Contents](#contents)
+    "###);
+}
+
+#[test]
+fn test_toc_headings() {
+    let doc = r###"
+```toml toc
+```
+
+Setext heading
+==============
+
+~~~
+# Not a heading
+~~~
+
+````
+```
+# Not a heading either
+```
+````
+
+> # Quoted, not listed
+
+## Closed heading ##
+
+#hashtag
+"###;
+
+    let mut document = doc.trim().to_string();
+    process_includes_document(&mut document, Path::new("")).unwrap();
+
+    assert_snapshot!(document, @r###"
+    - [Setext heading](#setext-heading)
+        - [Closed heading](#closed-heading)
+
+    Setext heading
+    ==============
+
+    ~~~
+    # Not a heading
+    ~~~
+
+    ````
+    ```
+    # Not a heading either
+    ```
+    ````
+
+    > # Quoted, not listed
+
+    ## Closed heading ##
+
+    #hashtag
+    "###);
+}
@@ -0,0 +1,213 @@
+use std::{cell::RefCell, path::Path, rc::Rc};
+
+use insta::assert_snapshot;
+
+use crate::{Banner, ErrorPolicy, FenceErrors, MemoryFileSystem, Mode, Processor, Status};
+
+#[test]
+fn test_fence_error_location() {
+    let files = MemoryFileSystem::new().with_file(
+        "docs/README.tpl.md",
+        "# Title\n\n```toml toc\nheader = \"x\"\nmax_depth = \"two\"\n```\n",
+    );
+    let error = Processor::new()
+        .file_system(files)
+        .render("docs/README.tpl.md")
+        .unwrap_err();
+
+    let errors = error.downcast_ref::<FenceErrors>().unwrap();
+    let location = &errors.errors[0].location;
+    assert_eq!(
+        location.template.as_deref(),
+        Some(Path::new("docs/README.tpl.md"))
+    );
+    assert_eq!((location.line, location.column), (5, 13));
+    assert_eq!(location.span, 46..51);
+    assert_snapshot!(error, @r###"
+    fence `toc`: invalid type: string "two", expected usize
+     --> docs/README.tpl.md:5:13
+      |
+    5 | max_depth = "two"
+      |             ^^^^^
+    "###);
+}
+
+const MISSING_SOURCE: &str = "# Title\n\n```toml rustdoc\nsource = \"src/missing.rs\"\n```\n";
+
+#[test]
+fn test_error_policy_defaults() {
+    let files = MemoryFileSystem::overlay().with_file("README.tpl.md", MISSING_SOURCE);
+    let processor = Processor::new()
+        .file_system(files.clone())
+        .banner(Banner::None);
+
+    processor
+        .mode(Mode::Update)
+        .process("README.tpl.md", "README.md")
+        .unwrap();
+    assert_snapshot!(files.get("README.md").unwrap(), @r###"
+    # Title
+
+    ```text
+    rustdoc: cannot open source file "src/missing.rs": failed to open file `src/missing.rs`: No such file or directory (os error 2)
+    ```
+    "###);
+
+    let processor = Processor::new().file_system(files).banner(Banner::None);
+    let report = processor.check_file("README.tpl.md", "README.md");
+    let Status::Failed(errors) = report.status else {
+        panic!("expected a failure, got {:?}", report.status);
+    };
+    assert_snapshot!(errors.join("\n"), @r###"
+    fence `rustdoc`: cannot open source file "src/missing.rs": failed to open file `src/missing.rs`: No such file or directory (os error 2)
+     --> README.tpl.md:3:1
+      |
+    3 | ```toml rustdoc
+      | ^^^^^^^^^^^^^^^
+    "###);
+}
+
+#[test]
+fn test_error_policy_per_fence() {
+    let files = MemoryFileSystem::overlay().with_file(
+        "README.tpl.md",
+        MISSING_SOURCE.replace("```\n", "on_error = \"fail\"\n```\n"),
+    );
+    let result = Processor::new()
+        .file_system(files.clone())
+        .mode(Mode::Update)
+        .on_error(ErrorPolicy::Inline)
+        .process("README.tpl.md", "README.md");
+    assert!(result.is_err());
+    assert!(files.get("README.md").is_none());
+}
+
+#[test]
+fn test_keep_previous_destination() {
+    let template = format!("{MISSING_SOURCE}\n## Usage\n");
+    let files = MemoryFileSystem::overlay()
+        .with_file("README.tpl.md", &template)
+        .with_file("README.md", "# Title\n\nPreviously generated\n\n## Usage\n");
+    let diagnostics = Rc::new(RefCell::new(Vec::new()));
+    let sink = diagnostics.clone();
+    let processor = Processor::new()
+        .file_system(files.clone())
+        .banner(Banner::None)
+        .mode(Mode::Check)
+        .on_error(ErrorPolicy::KeepPrevious)
+        .diagnostics(move |d| sink.borrow_mut().push(d.to_string()));
+    processor.process("README.tpl.md", "README.md").unwrap();
+
+    assert_snapshot!(diagnostics.borrow().join("\n"), @r###"
+    warning: keeping the previous content: cannot open source file "src/missing.rs": failed to open file `src/missing.rs`: No such file or directory (os error 2) (fence `rustdoc` at README.tpl.md:3)
+    "###);
+
+    // once the rest of the destination is out of date, the previous content cannot be found
+    files.insert("README.tpl.md", template.replace("# Title", "# New title"));
+    let report = processor.check_file("README.tpl.md", "README.md");
+    let Status::Failed(errors) = report.status else {
+        panic!("expected a failure, got {:?}", report.status);
+    };
+    assert_snapshot!(errors.join("\n"), @r###"
+    cannot keep the previous content of the failing fences, as the rest of "README.md" is out of date
+    "###);
+    assert_eq!(
+        files.get("README.md").unwrap(),
+        "# Title\n\nPreviously generated\n\n## Usage\n"
+    );
+}
+
+#[test]
+fn test_keep_previous_region() {
+    let files = MemoryFileSystem::overlay().with_file(
+        "README.md",
+        r###"# Title
+
+<!-- includes:toc -->
+<!-- /includes:toc -->
+
+<!-- includes:rustdoc
+source = "src/missing.rs"
+on_error = "keep-previous"
+-->
+
+Previously generated
+
+<!-- /includes:rustdoc -->
+"###,
+    );
+    Processor::new()
+        .file_system(files.clone())
+        .mode(Mode::Update)
+        .diagnostics(|_| {})
+        .process_in_place("README.md")
+        .unwrap();
+
+    assert_snapshot!(files.get("README.md").unwrap(), @r###"
+    # Title
+
+    <!-- includes:toc -->
+
+    - [Title](#title)
+
+    <!-- /includes:toc -->
+
+    <!-- includes:rustdoc
+    source = "src/missing.rs"
+    on_error = "keep-previous"
+    -->
+
+    Previously generated
+
+    <!-- /includes:rustdoc -->
+    "###);
+}
+
+const KEPT_FENCE: &str = r###"# Title
+
+<!-- includes:rustdoc
+source = "src/missing.rs"
+on_error = "keep-previous"
+-->
+
+An example:
+
+```toml toc
+```
+
+<!-- /includes:rustdoc -->
+"###;
+
+#[test]
+fn test_keep_previous_region_with_fence() {
+    let files = MemoryFileSystem::overlay().with_file("README.md", KEPT_FENCE);
+    Processor::new()
+        .file_system(files.clone())
+        .mode(Mode::Update)
+        .diagnostics(|_| {})
+        .process_in_place("README.md")
+        .unwrap();
+
+    assert_eq!(files.get("README.md").unwrap(), KEPT_FENCE);
+}
+
+#[test]
+fn test_keep_previous_deny_warnings() {
+    let files = MemoryFileSystem::overlay().with_file("README.md", KEPT_FENCE);
+    let error = Processor::new()
+        .file_system(files.clone())
+        .mode(Mode::Update)
+        .deny_warnings(true)
+        .diagnostics(|_| {})
+        .process_in_place("README.md")
+        .unwrap_err();
+
+    assert_snapshot!(error, @r###"
+    fence `rustdoc`: keeping the previous content: cannot open source file "src/missing.rs": failed to open file `src/missing.rs`: No such file or directory (os error 2)
+     --> README.md:3:1
+      |
+    3 | <!-- includes:rustdoc
+      | ^^^^^^^^^^^^^^^^^^^^^
+    "###);
+    assert_eq!(files.get("README.md").unwrap(), KEPT_FENCE);
+}
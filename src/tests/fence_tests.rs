@@ -0,0 +1,159 @@
+use std::path::Path;
+
+use anyhow::Result;
+use insta::assert_snapshot;
+use serde::Deserialize;
+
+use crate::{
+    fence::{Fence, FenceContext, FenceRegistry, FenceSection},
+    Processor,
+};
+
+#[derive(Deserialize)]
+struct Endpoints {
+    paths: Vec<String>,
+}
+
+impl Fence for Endpoints {
+    fn name() -> &'static str {
+        "api-endpoints"
+    }
+
+    fn priority(&self) -> u8 {
+        1
+    }
+
+    fn create(section: &FenceSection, _ctx: &FenceContext) -> Result<Box<Self>> {
+        Ok(Box::new(section.parse_config()?))
+    }
+
+    fn run(&self, _document: &str, _ctx: &FenceContext) -> Result<String> {
+        Ok(self
+            .paths
+            .iter()
+            .map(|p| format!("# {p}"))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
+
+struct NoToc;
+
+impl Fence for NoToc {
+    fn name() -> &'static str {
+        "toc"
+    }
+
+    fn priority(&self) -> u8 {
+        10
+    }
+
+    fn create(_section: &FenceSection, _ctx: &FenceContext) -> Result<Box<Self>> {
+        Ok(Box::new(NoToc))
+    }
+
+    fn run(&self, _document: &str, _ctx: &FenceContext) -> Result<String> {
+        Ok("No table of contents".to_string())
+    }
+}
+
+const DOC: &str = r###"
+```toml toc
+```
+
+```toml api-endpoints
+paths = ["/users", "/groups"]
+```
+
+```toml rustdoc
+source = "src/tests/rustdoc1.rs"
+```
+"###;
+
+#[test]
+fn test_registered_fence() {
+    let processor = Processor::new().fences(FenceRegistry::default().with::<Endpoints>());
+    let mut document = DOC.trim().to_string();
+    processor
+        .process_document(&mut document, Path::new(""))
+        .unwrap();
+
+    assert_snapshot!(document, @r###"
+    - [/users](#/users)
+    - [/groups](#/groups)
+
+    # /users
+    # /groups
+
+    Some rust doc here
+    ```rust
+    let s = String::new();
+    ```
+
+    Howdy
+    "###);
+}
+
+#[test]
+fn test_override_and_remove_builtin() {
+    let fences = FenceRegistry::default().with::<NoToc>().without("rustdoc");
+    assert_eq!(
+        fences.names().collect::<Vec<_>>(),
+        vec!["code", "include", "toc"]
+    );
+
+    let processor = Processor::new().fences(fences);
+    let mut document = DOC.trim().to_string();
+    processor
+        .process_document(&mut document, Path::new(""))
+        .unwrap();
+
+    assert_snapshot!(document, @r###"
+    No table of contents
+
+    ```toml api-endpoints
+    paths = ["/users", "/groups"]
+    ```
+
+    ```toml rustdoc
+    source = "src/tests/rustdoc1.rs"
+    ```
+    "###);
+}
+
+#[test]
+fn test_raw_fence() {
+    let mut document = r###"
+# Title
+
+```toml toc raw
+header = "# Table of contents"
+```
+
+~~~~toml api-endpoints raw
+paths = ["/users"]
+~~~~
+
+```toml toc
+```
+"###
+    .trim()
+    .to_string();
+    Processor::new()
+        .process_document(&mut document, Path::new(""))
+        .unwrap();
+
+    assert_snapshot!(document, @r###"
+    # Title
+
+    ```toml toc
+    header = "# Table of contents"
+    ```
+
+    ~~~~toml api-endpoints
+    paths = ["/users"]
+    ~~~~
+
+    - [Title](#title)
+    "###);
+}
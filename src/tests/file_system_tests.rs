@@ -0,0 +1,91 @@
+use insta::assert_snapshot;
+
+use crate::{Banner, FileSystem, MemoryFileSystem, Mode, Processor};
+
+#[test]
+fn test_render_from_memory() {
+    let files = MemoryFileSystem::new()
+        .with_file(
+            "docs/README.tpl.md",
+            "# Title\n\n```toml toc\n```\n\n```toml rustdoc\nsource = \"../src/lib.rs\"\n```\n",
+        )
+        .with_file(
+            "src/lib.rs",
+            "//! ## Usage\n//!\n//! See [`Item`](crate::inner::Item).\n\nmod inner;\n",
+        )
+        .with_file("src/inner.rs", "pub struct Item;\n");
+
+    let processor = Processor::new()
+        .file_system(files.clone())
+        .banner(Banner::None)
+        .mode(Mode::Update);
+    processor
+        .process("docs/README.tpl.md", "docs/README.md")
+        .unwrap();
+
+    assert_snapshot!(files.get("docs/README.md").unwrap(), @r###"
+    # Title
+
+    - [Title](#title)
+        - [Usage](#usage)
+
+    ## Usage
+
+    See [`Item`](https://docs.rs/markdown-includes/latest/markdown_includes/inner/struct.Item.html).
+    "###);
+    assert_eq!(
+        processor.dependencies().paths().collect::<Vec<_>>(),
+        [
+            "docs/../src/inner.rs",
+            "docs/../src/lib.rs",
+            "docs/README.tpl.md"
+        ]
+        .map(std::path::Path::new)
+    );
+}
+
+#[test]
+fn test_memory_file_system() {
+    let files = MemoryFileSystem::new().with_file("./a/b/../c.md", "C");
+
+    assert!(files.is_file("a/c.md".as_ref()));
+    assert_eq!(files.read_to_string("a/./c.md".as_ref()).unwrap(), "C");
+    assert!(!files.is_file("Cargo.toml".as_ref()));
+    assert!(files.read_to_string("Cargo.toml".as_ref()).is_err());
+
+    let overlay = MemoryFileSystem::overlay().with_file("Cargo.toml", "unsaved");
+    assert_eq!(
+        overlay.read_to_string("Cargo.toml".as_ref()).unwrap(),
+        "unsaved"
+    );
+    assert!(overlay
+        .read_to_string("src/lib.rs".as_ref())
+        .unwrap()
+        .starts_with("//!"));
+}
+
+#[test]
+fn test_standard_library_from_disk() {
+    let files = MemoryFileSystem::new()
+        .with_file(
+            "README.tpl.md",
+            "```toml rustdoc\nsource = \"src/lib.rs\"\n```\n",
+        )
+        .with_file("src/lib.rs", "//! See [`String`](::std::string::String).\n");
+
+    let processor = Processor::new().file_system(files).banner(Banner::None);
+    let document = processor.render("README.tpl.md").unwrap();
+    // the sources of the standard library are only there with the rust-src component
+    assert!(!document.contains("not found in memory"), "{document}");
+    if document.contains("doc.rust-lang.org") {
+        let dependencies = processor.dependencies();
+        assert!(dependencies
+            .paths()
+            .any(|path| path.components().any(|c| c.as_os_str() == "rustlib")));
+    } else {
+        assert!(
+            document.contains("Cannot find rust standard library"),
+            "{document}"
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use std::path::Path;
+
+use fs_err as fs;
+use insta::assert_snapshot;
+
+use super::temp_dir;
+use crate::{MemoryFileSystem, Mode, Processor};
+
+const README: &str = r###"# Title
+
+<!-- includes:toc
+header = "## Contents"
+-->
+- [Outdated](#outdated)
+<!-- /includes:toc -->
+
+<!-- includes:rustdoc source = "src/tests/rustdoc2.rs" -->
+<!-- /includes:rustdoc -->
+
+```markdown
+<!-- includes:toc -->
+```
+
+# Footer
+"###;
+
+#[test]
+fn test_in_place() {
+    let processor = Processor::new();
+    let mut document = README.to_string();
+    processor
+        .process_document_in_place(&mut document, Path::new(""))
+        .unwrap();
+
+    assert_snapshot!(document, @r###"
+    # Title
+
+    <!-- includes:toc
+    header = "## Contents"
+    -->
+
+    ## Contents
+
+    - [Title](#title)
+    - [Second crate](#second-crate)
+        - [Usage](#usage)
+    - [Footer](#footer)
+
+    <!-- /includes:toc -->
+
+    <!-- includes:rustdoc source = "src/tests/rustdoc2.rs" -->
+
+    # Second crate
+
+    A longer piece of rust doc, which makes sure that
+    the fences after it are moved accordingly.
+
+    ## Usage
+
+    Call it.
+
+    <!-- /includes:rustdoc -->
+
+    ```markdown
+    <!-- includes:toc -->
+    ```
+
+    # Footer
+    "###);
+
+    let regenerated = {
+        let mut doc = document.clone();
+        processor
+            .process_document_in_place(&mut doc, Path::new(""))
+            .unwrap();
+        doc
+    };
+    assert_eq!(regenerated, document);
+}
+
+#[test]
+fn test_missing_end_marker() {
+    let mut document = "<!-- includes:toc -->\n\n# Title\n".to_string();
+    let err = Processor::new()
+        .process_document_in_place(&mut document, Path::new(""))
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "missing end marker `<!-- /includes:toc -->` for the marker at line 1"
+    );
+}
+
+#[test]
+fn test_end_marker_of_another_fence() {
+    let mut document = "<!-- includes:toc -->\n\n# B\n\n\
+        <!-- includes:rustdoc source = \"src/tests/rustdoc2.rs\" -->\nOld\n<!-- /includes:rustdoc -->\n"
+        .to_string();
+    let err = Processor::new()
+        .process_document_in_place(&mut document, Path::new(""))
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "missing end marker `<!-- /includes:toc -->` for the marker at line 1"
+    );
+}
+
+#[test]
+fn test_marker_inside_region() {
+    let mut document = "<!-- includes:toc -->\n\
+        <!-- includes:rustdoc source = \"src/tests/rustdoc2.rs\" -->\n<!-- /includes:rustdoc -->\n\
+        <!-- /includes:toc -->\n"
+        .to_string();
+    let err = Processor::new()
+        .process_document_in_place(&mut document, Path::new(""))
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "the marker at line 2 is inside the region of the marker at line 1, which ends at line 4"
+    );
+}
+
+#[test]
+fn test_update_same_file() {
+    let dir = temp_dir("update_same_file");
+    let readme = "# Title\n\n<!-- includes:toc -->\n<!-- /includes:toc -->\n\n## Section\n";
+    fs::write(dir.join("README.md"), readme).unwrap();
+
+    Processor::new()
+        .base_dir(&dir)
+        .mode(Mode::Update)
+        .process("README.md", "README.md")
+        .unwrap();
+
+    assert_snapshot!(fs::read_to_string(dir.join("README.md")).unwrap(), @r###"
+    # Title
+
+    <!-- includes:toc -->
+
+    - [Title](#title)
+        - [Section](#section)
+
+    <!-- /includes:toc -->
+
+    ## Section
+    "###);
+}
+
+#[test]
+fn test_update_same_file_with_aliases() {
+    let readme =
+        "# Title\n\n<!-- includes:toc -->\n- [Outdated](#outdated)\n<!-- /includes:toc -->\n";
+    for (template, destination) in [
+        ("./README.md", "README.md"),
+        ("docs/../README.md", "./README.md"),
+    ] {
+        let files = MemoryFileSystem::new().with_file("README.md", readme);
+        Processor::new()
+            .file_system(files.clone())
+            .mode(Mode::Update)
+            .process(template, destination)
+            .unwrap();
+
+        assert_eq!(
+            files.get("README.md").unwrap(),
+            "# Title\n\n<!-- includes:toc -->\n\n- [Title](#title)\n\n<!-- /includes:toc -->\n",
+            "{template} -> {destination}"
+        );
+    }
+}
+
+#[test]
+fn test_render_in_place() {
+    let readme =
+        "# Title\n\n<!-- includes:toc -->\n- [Outdated](#outdated)\n<!-- /includes:toc -->\n";
+    let files = MemoryFileSystem::new().with_file("README.md", readme);
+
+    let document = Processor::new()
+        .file_system(files.clone())
+        .render("README.md")
+        .unwrap();
+    assert_eq!(
+        document,
+        "# Title\n\n<!-- includes:toc -->\n\n- [Title](#title)\n\n<!-- /includes:toc -->\n"
+    );
+    assert_eq!(files.get("README.md").unwrap(), readme);
+}
@@ -0,0 +1,137 @@
+use insta::assert_snapshot;
+
+use crate::{Banner, MemoryFileSystem, Mode, Processor};
+
+const GUIDE: &str = "---
+title: Guide
+---
+# Guide
+
+Setting up.
+
+## Install
+
+Run the installer.
+
+Notes
+-----
+
+Works offline.
+";
+
+/// Renders `template` as _docs/README.tpl.md_, next to a _docs/guide.md_
+fn render(template: &str) -> String {
+    let files = MemoryFileSystem::new()
+        .with_file("docs/README.tpl.md", template)
+        .with_file("docs/guide.md", GUIDE);
+    Processor::new()
+        .file_system(files.clone())
+        .banner(Banner::None)
+        .mode(Mode::Update)
+        .process("docs/README.tpl.md", "docs/README.md")
+        .unwrap();
+    files.get("docs/README.md").unwrap()
+}
+
+#[test]
+fn test_include() {
+    let output = render("# Project\n\n```toml include\nsource = \"guide.md\"\n```\n");
+
+    assert_snapshot!(output, @r###"
+    # Project
+
+    # Guide
+
+    Setting up.
+
+    ## Install
+
+    Run the installer.
+
+    Notes
+    -----
+
+    Works offline.
+    "###);
+}
+
+#[test]
+fn test_include_shift_headings() {
+    let output = render(
+        "# Project\n\n```toml toc\n```\n\n```toml file\nsource = \"guide.md\"\nshift_headings = 1\n```\n",
+    );
+
+    assert_snapshot!(output, @r###"
+    # Project
+
+    - [Project](#project)
+        - [Guide](#guide)
+            - [Install](#install)
+            - [Notes](#notes)
+
+    ## Guide
+
+    Setting up.
+
+    ### Install
+
+    Run the installer.
+
+    ### Notes
+
+    Works offline.
+    "###);
+}
+
+#[test]
+fn test_include_missing_file() {
+    let output = render("```toml include\nsource = \"missing.md\"\n```\n");
+
+    assert_snapshot!(output, @r###"
+    ```text
+    include: cannot include "docs/missing.md": file "docs/missing.md" not found in memory
+    ```
+    "###);
+}
+
+#[test]
+fn test_include_section() {
+    let output = render(
+        "# Project\n\n```toml include\nsource = \"guide.md\"\nsection = \"Install\"\nshift_headings = 1\n```\n\n## License\n",
+    );
+
+    assert_snapshot!(output, @r###"
+    # Project
+
+    ### Install
+
+    Run the installer.
+
+    ## License
+    "###);
+}
+
+#[test]
+fn test_include_section_path_without_heading() {
+    let output = render(
+        "# Project\n\n```toml include\nsource = \"guide.md\"\nsection = \"Guide > Notes\"\ndrop_heading = true\n```\n",
+    );
+
+    assert_snapshot!(output, @r###"
+    # Project
+
+    Works offline.
+    "###);
+}
+
+#[test]
+fn test_include_missing_section() {
+    let output =
+        render("```toml include\nsource = \"guide.md\"\nsection = \"Notes > Install\"\n```\n");
+
+    assert_snapshot!(output, @r###"
+    ```text
+    include: in "docs/guide.md": no section `Notes > Install`, the headings are: Guide, Guide > Install, Guide > Notes
+    ```
+    "###);
+}
@@ -1,2 +1,32 @@
+use std::{env, path::PathBuf};
+
+use fs_err as fs;
+
+mod code_tests;
+mod comment_tests;
+mod config_tests;
+mod dependencies_tests;
+mod diff_tests;
+mod engine_tests;
+mod error_tests;
+mod fence_tests;
+mod file_system_tests;
+mod in_place_tests;
+mod include_tests;
+mod nested_tests;
+mod processor_tests;
+mod report_tests;
 mod rustdoc_tests;
+mod strict_tests;
 mod toc_tests;
+mod watch_tests;
+
+/// An empty directory for a test to write files in
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join("markdown-includes-tests").join(name);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
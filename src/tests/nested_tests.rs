@@ -0,0 +1,170 @@
+use fs_err as fs;
+use insta::assert_snapshot;
+
+use super::temp_dir;
+use crate::{Banner, MemoryFileSystem, Processor};
+
+const TEMPLATE: &str = r###"# Title
+
+```toml rustdoc
+source = "src/lib.rs"
+```
+"###;
+
+#[test]
+fn test_nested_fences() {
+    let dir = temp_dir("nested_fences");
+    fs::create_dir_all(dir.join("src/inner")).unwrap();
+    fs::write(dir.join("README.tpl.md"), TEMPLATE).unwrap();
+    fs::write(
+        dir.join("src/lib.rs"),
+        r###"//! ## Crate
+//!
+//! ```toml toc
+//! ```
+//!
+//! ```toml rustdoc
+//! source = "inner/mod.rs"
+//! ```
+"###,
+    )
+    .unwrap();
+    fs::write(
+        dir.join("src/inner/mod.rs"),
+        "//! ## Inner\n//!\n//! Included from the crate docs.\n",
+    )
+    .unwrap();
+
+    let rendered = Processor::new()
+        .base_dir(&dir)
+        .banner(Banner::None)
+        .render("README.tpl.md")
+        .unwrap();
+    assert_snapshot!(rendered, @r###"
+    # Title
+
+    ## Crate
+
+    - [Title](#title)
+        - [Crate](#crate)
+        - [Inner](#inner)
+
+    ## Inner
+
+    Included from the crate docs.
+    "###);
+}
+
+#[test]
+fn test_include_cycle() {
+    let dir = temp_dir("include_cycle");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("README.tpl.md"), TEMPLATE).unwrap();
+    fs::write(
+        dir.join("src/lib.rs"),
+        "//! ```toml rustdoc\n//! source = \"other.rs\"\n//! ```\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("src/other.rs"),
+        "//! ```toml rustdoc\n//! source = \"lib.rs\"\n//! ```\n",
+    )
+    .unwrap();
+
+    let err = Processor::new()
+        .base_dir(&dir)
+        .render("README.tpl.md")
+        .unwrap_err();
+    let err = err.to_string().replace(dir.to_str().unwrap(), "");
+    assert_snapshot!(err, @r###"
+    fence `rustdoc`: include cycle: "/README.tpl.md" -> "/src/lib.rs" -> "/src/other.rs" -> "/src/lib.rs"
+     --> README.tpl.md:3:1
+      |
+    3 | ```toml rustdoc
+      | ^^^^^^^^^^^^^^^
+    "###);
+}
+
+#[test]
+fn test_max_depth() {
+    let dir = temp_dir("max_depth");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("README.tpl.md"), TEMPLATE).unwrap();
+    fs::write(
+        dir.join("src/lib.rs"),
+        "//! ```toml rustdoc\n//! source = \"other.rs\"\n//! ```\n",
+    )
+    .unwrap();
+    fs::write(dir.join("src/other.rs"), "//! Other\n").unwrap();
+
+    let processor = |max_depth| {
+        Processor::new()
+            .base_dir(&dir)
+            .banner(Banner::None)
+            .max_depth(max_depth)
+    };
+    assert_eq!(
+        processor(1).render("README.tpl.md").unwrap(),
+        "# Title\n\nOther\n"
+    );
+
+    let err = processor(0).render("README.tpl.md").unwrap_err();
+    let err = err.to_string().replace(dir.to_str().unwrap(), "");
+    assert_snapshot!(err, @r###"
+    fence `rustdoc`: includes are nested deeper than the maximum depth of 0: "/src/lib.rs"
+     --> README.tpl.md:3:1
+      |
+    3 | ```toml rustdoc
+      | ^^^^^^^^^^^^^^^
+    "###);
+}
+
+#[test]
+fn test_template_including_itself() {
+    let files = MemoryFileSystem::new()
+        .with_file(
+            "docs/README.tpl.md",
+            "# Title\n\n```toml include\nsource = \"a.md\"\n```\n",
+        )
+        .with_file(
+            "docs/a.md",
+            "A\n\n```toml include\nsource = \"README.tpl.md\"\n```\n",
+        );
+
+    let err = Processor::new()
+        .file_system(files)
+        .render("docs/README.tpl.md")
+        .unwrap_err();
+    assert_snapshot!(err, @r###"
+    fence `include`: include cycle: "docs/README.tpl.md" -> "docs/a.md" -> "docs/README.tpl.md"
+     --> docs/README.tpl.md:3:1
+      |
+    3 | ```toml include
+      | ^^^^^^^^^^^^^^^
+    "###);
+}
+
+#[test]
+fn test_include_cycle_in_memory() {
+    let files = MemoryFileSystem::new()
+        .with_file(
+            "docs/README.tpl.md",
+            "```toml include\nsource = \"a.md\"\n```\n",
+        )
+        .with_file(
+            "docs/a.md",
+            "A\n\n```toml include\nsource = \"../docs/a.md\"\n```\n",
+        );
+
+    let err = Processor::new()
+        .file_system(files)
+        .render("docs/README.tpl.md")
+        .unwrap_err();
+    assert_snapshot!(err, @r###"
+    fence `include`: include cycle: "docs/README.tpl.md" -> "docs/a.md" -> "docs/../docs/a.md"
+     --> docs/README.tpl.md:1:1
+      |
+    1 | ```toml include
+      | ^^^^^^^^^^^^^^^
+    "###);
+}
@@ -0,0 +1,238 @@
+use std::{cell::RefCell, path::PathBuf, rc::Rc};
+
+use fs_err as fs;
+use insta::assert_snapshot;
+
+use super::temp_dir;
+use crate::{Banner, BannerPosition, Diagnostic, MemoryFileSystem, Mode, Processor, Severity};
+
+const TEMPLATE: &str = r###"# Title
+
+```toml toc
+```
+
+## Section
+"###;
+
+#[test]
+fn test_render_without_banner() {
+    let dir = temp_dir("render_without_banner");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src/README.tpl.md"), TEMPLATE).unwrap();
+
+    let processor = Processor::new().base_dir(&dir).banner(Banner::None);
+    let rendered = processor.render("src/README.tpl.md").unwrap();
+
+    assert_snapshot!(rendered, @r###"
+    # Title
+
+    - [Title](#title)
+        - [Section](#section)
+
+    ## Section
+    "###);
+}
+
+#[test]
+fn test_modes() {
+    let dir = temp_dir("modes");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src/README.tpl.md"), TEMPLATE).unwrap();
+
+    let processor = |mode| Processor::new().base_dir(&dir).mode(mode);
+
+    processor(Mode::DryRun)
+        .process("src/README.tpl.md", "README.md")
+        .unwrap();
+    assert!(!dir.join("README.md").exists());
+
+    let err = processor(Mode::Check)
+        .process("src/README.tpl.md", "README.md")
+        .unwrap_err();
+    assert!(err.to_string().contains("out of sync"));
+
+    fs::write(dir.join("README.md"), "# Old title").unwrap();
+    let err = processor(Mode::Check)
+        .process("src/README.tpl.md", "README.md")
+        .unwrap_err();
+    assert!(err.to_string().contains("-# Old title"));
+
+    processor(Mode::Update)
+        .process_all([("src/README.tpl.md", "README.md")])
+        .unwrap();
+    assert_ne!(
+        fs::read_to_string(dir.join("README.md")).unwrap(),
+        "# Old title"
+    );
+
+    processor(Mode::Check)
+        .process("src/README.tpl.md", "README.md")
+        .unwrap();
+}
+
+#[test]
+fn test_diagnostics_sink() {
+    let mut document = r###"
+```toml rustdoc
+source = "src/tests/rustdoc_intralinks.rs"
+```
+"###
+    .trim()
+    .to_string();
+
+    let diagnostics = Rc::new(RefCell::new(Vec::new()));
+    let sink = diagnostics.clone();
+    Processor::new()
+        .diagnostics(move |d| sink.borrow_mut().push(d.to_string()))
+        .process_document(&mut document, "".as_ref())
+        .unwrap();
+
+    assert_eq!(document, "Links to a missing item.");
+    assert_eq!(
+        *diagnostics.borrow(),
+        vec!["warning: Could not resolve definition of `crate::Missing`. (fence `rustdoc` at line 1)"]
+    );
+}
+
+#[test]
+fn test_deny_warnings() {
+    let files = MemoryFileSystem::overlay().with_file(
+        "README.tpl.md",
+        "# Title\n\n```toml rustdoc\nsource = \"src/tests/rustdoc_intralinks.rs\"\n```\n",
+    );
+    let diagnostics = Rc::new(RefCell::new(Vec::new()));
+    let sink = diagnostics.clone();
+    let processor = Processor::new()
+        .file_system(files)
+        .diagnostics(move |d| sink.borrow_mut().push(d.clone()))
+        .deny_warnings(true);
+
+    let err = processor.render("README.tpl.md").unwrap_err();
+    assert_snapshot!(err, @r###"
+    fence `rustdoc`: reported errors: Could not resolve definition of `crate::Missing`.
+     --> README.tpl.md:3:1
+      |
+    3 | ```toml rustdoc
+      | ^^^^^^^^^^^^^^^
+    "###);
+    assert_eq!(
+        *diagnostics.borrow(),
+        vec![Diagnostic {
+            severity: Severity::Error,
+            message: "Could not resolve definition of `crate::Missing`.".to_string(),
+            symbol: Some("crate::Missing".to_string()),
+            template: Some(PathBuf::from("README.tpl.md")),
+            fence: Some("rustdoc".to_string()),
+            line: Some(3),
+        }]
+    );
+}
+
+#[test]
+fn test_mode_from_str() {
+    assert_eq!("update".parse::<Mode>().unwrap(), Mode::Update);
+    assert_eq!("Check".parse::<Mode>().unwrap(), Mode::Check);
+    assert_eq!("dry-run".parse::<Mode>().unwrap(), Mode::DryRun);
+    assert_eq!("bless".parse::<Mode>().unwrap(), Mode::Bless);
+
+    let err = "verify".parse::<Mode>().unwrap_err();
+    assert_snapshot!(err, @r###"
+    unknown mode `verify`, expected one of update, check, dry-run or bless
+    "###);
+}
+
+#[test]
+fn test_bless_writes_whitespace_changes() {
+    let dir = temp_dir("bless");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src/README.tpl.md"), TEMPLATE).unwrap();
+
+    let processor = |mode| {
+        Processor::new()
+            .base_dir(&dir)
+            .banner(Banner::None)
+            .mode(mode)
+    };
+    processor(Mode::Update)
+        .process("src/README.tpl.md", "README.md")
+        .unwrap();
+    let generated = fs::read_to_string(dir.join("README.md")).unwrap();
+    let with_trailing_spaces = generated.replace("# Title", "# Title  ");
+    fs::write(dir.join("README.md"), &with_trailing_spaces).unwrap();
+
+    // whitespace differences are not out of sync, so they are kept
+    processor(Mode::Check)
+        .process("src/README.tpl.md", "README.md")
+        .unwrap();
+    processor(Mode::Update)
+        .process("src/README.tpl.md", "README.md")
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(dir.join("README.md")).unwrap(),
+        with_trailing_spaces
+    );
+
+    processor(Mode::Bless)
+        .process("src/README.tpl.md", "README.md")
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(dir.join("README.md")).unwrap(),
+        generated
+    );
+}
+
+#[test]
+fn test_custom_banner_after_front_matter() {
+    let dir = temp_dir("custom_banner");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("src/guide.tpl.md"),
+        format!("---\ntitle: Guide\n---\n{TEMPLATE}"),
+    )
+    .unwrap();
+
+    let processor = |position| {
+        Processor::new()
+            .base_dir(&dir)
+            .banner(Banner::Custom(
+                "<!-- Generated from {template} by `{command}` -->".to_string(),
+            ))
+            .banner_position(position)
+            .regenerate_command("cargo test update_docs")
+    };
+
+    let rendered = processor(BannerPosition::AfterFrontMatter)
+        .render("src/guide.tpl.md")
+        .unwrap();
+    assert_snapshot!(rendered, @r###"
+    ---
+    title: Guide
+    ---
+    <!-- Generated from src/guide.tpl.md by `cargo test update_docs` -->
+    # Title
+
+    - [Title](#title)
+        - [Section](#section)
+
+    ## Section
+    "###);
+
+    let rendered = processor(BannerPosition::Top)
+        .render("src/guide.tpl.md")
+        .unwrap();
+    assert!(rendered.starts_with("<!-- Generated from src/guide.tpl.md"));
+}
+
+#[test]
+fn test_banner_after_missing_front_matter() {
+    let dir = temp_dir("banner_without_front_matter");
+    fs::write(dir.join("README.tpl.md"), "---\nnot front matter\n").unwrap();
+
+    let rendered = Processor::new()
+        .base_dir(&dir)
+        .banner(Banner::Custom("<!-- banner -->".to_string()))
+        .banner_position(BannerPosition::AfterFrontMatter)
+        .render("README.tpl.md")
+        .unwrap();
+    assert_eq!(rendered, "<!-- banner -->\n---\nnot front matter\n");
+}
@@ -0,0 +1,189 @@
+use fs_err as fs;
+use insta::assert_snapshot;
+
+use super::temp_dir;
+use crate::{Banner, Processor, ProjectConfig, Report, Status};
+
+const TEMPLATE: &str = r###"# Title
+
+```toml toc
+```
+
+## Section
+"###;
+
+const GENERATED: &str = r###"# Title
+
+- [Title](#title)
+    - [Section](#section)
+
+## Section
+"###;
+
+#[test]
+fn test_check_file_statuses() {
+    let dir = temp_dir("report_statuses");
+    fs::write(dir.join("README.tpl.md"), TEMPLATE).unwrap();
+    let processor = Processor::new().base_dir(&dir).banner(Banner::None);
+
+    let report = processor.check_file("README.tpl.md", "README.md");
+    assert_eq!(report.status, Status::WouldCreate);
+
+    fs::write(dir.join("README.md"), GENERATED).unwrap();
+    let report = processor.check_file("README.tpl.md", "README.md");
+    assert_eq!(report.status, Status::UpToDate);
+
+    fs::write(dir.join("README.md"), GENERATED.replace("Section", "Old")).unwrap();
+    let report = processor.check_file("README.tpl.md", "README.md");
+    let Status::WouldChange(diff) = report.status else {
+        panic!("expected a change, got {:?}", report.status);
+    };
+    assert_snapshot!(diff, @r###"
+    @@ -1,6 +1,6 @@
+     # Title
+     
+     - [Title](#title)
+    -    - [Old](#section)
+    +    - [Section](#section)
+     
+    -## Old
+    +## Section
+    "###);
+
+    // nothing is written when checking
+    assert!(fs::read_to_string(dir.join("README.md"))
+        .unwrap()
+        .contains("## Old"));
+}
+
+#[test]
+fn test_check_file_failed_lists_all_fence_errors() {
+    let dir = temp_dir("report_failed");
+    fs::write(
+        dir.join("README.tpl.md"),
+        "```toml toc\nheader = 1\n```\n\n```toml toc\nunknown = \n```\n",
+    )
+    .unwrap();
+    let processor = Processor::new().base_dir(&dir).banner(Banner::None);
+
+    let report = processor.check_file("README.tpl.md", "README.md");
+    let Status::Failed(errors) = report.status else {
+        panic!("expected a failure, got {:?}", report.status);
+    };
+    assert_eq!(errors.len(), 2);
+    assert_snapshot!(errors.join("\n"), @r###"
+    fence `toc`: invalid type: integer `1`, expected a string
+     --> README.tpl.md:2:10
+      |
+    2 | header = 1
+      |          ^
+    fence `toc`: invalid string
+    expected `"`, `'`
+     --> README.tpl.md:6:11
+      |
+    6 | unknown = 
+      |           ^
+    "###);
+}
+
+#[test]
+fn test_report_aggregation_and_display() {
+    let dir = temp_dir("report_aggregation");
+    fs::write(dir.join("a.tpl.md"), TEMPLATE).unwrap();
+    fs::write(dir.join("b.tpl.md"), TEMPLATE).unwrap();
+    fs::write(dir.join("a.md"), GENERATED).unwrap();
+    let processor = Processor::new().base_dir(&dir).banner(Banner::None);
+
+    let mut report: Report = processor.check_all([("a.tpl.md", "a.md")]);
+    assert!(report.is_up_to_date());
+
+    report.extend([processor.check_file("b.tpl.md", "b.md")]);
+    assert!(!report.is_up_to_date());
+    assert_eq!(
+        report
+            .outdated()
+            .map(|f| f.destination.to_str().unwrap())
+            .collect::<Vec<_>>(),
+        ["b.md"]
+    );
+
+    assert_snapshot!(report, @r###"
+    a.md: up to date
+    b.md: would be created
+    "###);
+}
+
+#[test]
+fn test_check_project() {
+    let dir = temp_dir("report_project");
+    fs::write(dir.join("README.tpl.md"), TEMPLATE).unwrap();
+    fs::write(dir.join("README.md"), GENERATED).unwrap();
+    fs::write(
+        dir.join(ProjectConfig::FILE_NAME),
+        "[[files]]\ntemplate = \"README.tpl.md\"\ndestination = \"README.md\"\n",
+    )
+    .unwrap();
+
+    let project = ProjectConfig::load(&dir).unwrap();
+    let report = Processor::new()
+        .banner(Banner::None)
+        .check_project(&project);
+    assert!(report.is_up_to_date(), "{report}");
+    assert_eq!(report.files[0].destination, dir.join("README.md"));
+}
+
+#[test]
+fn test_report_serialization() {
+    let dir = temp_dir("report_serialization");
+    fs::write(dir.join("README.tpl.md"), TEMPLATE).unwrap();
+    fs::write(dir.join("README.md"), GENERATED.replace("# Title", "# Old")).unwrap();
+    let processor = Processor::new().base_dir(&dir).banner(Banner::None);
+
+    let report = processor.check_all([("README.tpl.md", "README.md")]);
+    let json = serde_json::to_string_pretty(&report).unwrap();
+    assert_snapshot!(json, @r###"
+    {
+      "files": [
+        {
+          "template": "README.tpl.md",
+          "destination": "README.md",
+          "status": {
+            "status": "would_change",
+            "details": {
+              "hunks": [
+                {
+                  "old_start": 1,
+                  "old_len": 4,
+                  "new_start": 1,
+                  "new_len": 4,
+                  "lines": [
+                    {
+                      "kind": "removed",
+                      "line": "# Old"
+                    },
+                    {
+                      "kind": "added",
+                      "line": "# Title"
+                    },
+                    {
+                      "kind": "context",
+                      "line": ""
+                    },
+                    {
+                      "kind": "context",
+                      "line": "- [Title](#title)"
+                    },
+                    {
+                      "kind": "context",
+                      "line": "    - [Section](#section)"
+                    }
+                  ]
+                }
+              ]
+            }
+          }
+        }
+      ]
+    }
+    "###);
+}
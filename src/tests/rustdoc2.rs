@@ -0,0 +1,8 @@
+//! # Second crate
+//!
+//! A longer piece of rust doc, which makes sure that
+//! the fences after it are moved accordingly.
+//!
+//! ## Usage
+//!
+//! Call it.
@@ -0,0 +1 @@
+//! Links to [a missing item](crate::Missing).
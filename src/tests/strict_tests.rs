@@ -0,0 +1,86 @@
+use std::path::Path;
+
+use insta::assert_snapshot;
+
+use crate::Processor;
+
+fn process_strict(document: &str) -> String {
+    let mut document = document.trim().to_string();
+    match Processor::new()
+        .strict(true)
+        .process_document(&mut document, Path::new(""))
+    {
+        Ok(()) => document,
+        Err(e) => e.to_string(),
+    }
+}
+
+#[test]
+fn test_unknown_fence() {
+    let template = "# Title\n\n```toml tco\n```\n";
+
+    let mut document = template.to_string();
+    Processor::new()
+        .process_document(&mut document, Path::new(""))
+        .unwrap();
+    assert_eq!(document, template);
+
+    assert_snapshot!(process_strict(template), @r###"
+    fence `tco`: unknown fence `tco`, did you mean `toc`?
+     --> 3:1
+      |
+    3 | ```toml tco
+      | ^^^^^^^^^^^
+    "###);
+}
+
+#[test]
+fn test_unrelated_toml_block() {
+    let template = "```toml title\nname = \"example\"\n```";
+    assert_eq!(process_strict(template), template);
+}
+
+#[test]
+fn test_unknown_key() {
+    assert_snapshot!(process_strict("# Title\n\n```toml toc\nmax_deph = 2\n```\n"), @r###"
+    fence `toc`: unknown key `max_deph`, did you mean `max_depth`?
+     --> 4:1
+      |
+    4 | max_deph = 2
+      | ^^^^^^^^
+    "###);
+    assert_snapshot!(process_strict(r#"
+```toml rustdoc
+source = "src/tests/rustdoc1.rs"
+intralinks.strip_link = true
+```
+"#), @r###"
+    fence `rustdoc`: unknown key `intralinks.strip_link`, did you mean `strip_links`?
+     --> 3:1
+      |
+    3 | intralinks.strip_link = true
+      | ^^^^^^^^^^^^^^^^^^^^^
+    "###);
+    assert_snapshot!(process_strict("```toml toc\nvery_different = 2\n```\n"), @r###"
+    fence `toc`: unknown key `very_different`
+     --> 2:1
+      |
+    2 | very_different = 2
+      | ^^^^^^^^^^^^^^
+    "###);
+}
+
+#[test]
+fn test_unknown_directive() {
+    let mut document = "<!-- includes:tco -->\n".to_string();
+    let error = Processor::new()
+        .process_document(&mut document, Path::new(""))
+        .unwrap_err();
+    assert_snapshot!(error, @r###"
+    fence `tco`: unknown fence `tco`, did you mean `toc`?
+     --> 1:1
+      |
+    1 | <!-- includes:tco -->
+      | ^^^^^^^^^^^^^^^^^^^^^
+    "###);
+}
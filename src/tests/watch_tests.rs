@@ -0,0 +1,64 @@
+use fs_err as fs;
+
+use super::temp_dir;
+use crate::{Banner, Mode, Processor, Watcher};
+
+fn processed(results: &[crate::FileResult]) -> Vec<&str> {
+    results
+        .iter()
+        .map(|r| {
+            assert!(r.result.is_ok(), "{:?}", r.result);
+            r.destination.to_str().unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn test_watcher_regenerates_changed_inputs() {
+    let dir = temp_dir("watcher");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("a.tpl.md"),
+        "```toml rustdoc\nsource = \"src/lib.rs\"\n```\n",
+    )
+    .unwrap();
+    fs::write(dir.join("b.tpl.md"), "# B\n").unwrap();
+    fs::write(dir.join("src/lib.rs"), "//! First\n").unwrap();
+
+    let processor = Processor::new()
+        .base_dir(&dir)
+        .banner(Banner::None)
+        .mode(Mode::Update);
+    let mut watcher = Watcher::new(processor, [("a.tpl.md", "a.md"), ("b.tpl.md", "b.md")]);
+
+    assert_eq!(processed(&watcher.poll()), ["a.md", "b.md"]);
+    assert!(watcher.poll().is_empty());
+
+    // a dependency of the rustdoc fence
+    fs::write(dir.join("src/lib.rs"), "//! Second version\n").unwrap();
+    assert_eq!(processed(&watcher.poll()), ["a.md"]);
+    assert_eq!(
+        fs::read_to_string(dir.join("a.md")).unwrap(),
+        "Second version\n"
+    );
+
+    fs::write(dir.join("b.tpl.md"), "# B changed\n").unwrap();
+    assert_eq!(processed(&watcher.poll()), ["b.md"]);
+    assert!(watcher.poll().is_empty());
+}
+
+#[test]
+fn test_watcher_retries_missing_template() {
+    let dir = temp_dir("watcher_missing");
+    let processor = Processor::new()
+        .base_dir(&dir)
+        .banner(Banner::None)
+        .mode(Mode::Update);
+    let mut watcher = Watcher::new(processor, [("README.tpl.md", "README.md")]);
+
+    assert!(watcher.poll()[0].result.is_err());
+    assert!(watcher.poll().is_empty());
+
+    fs::write(dir.join("README.tpl.md"), "# Title\n").unwrap();
+    assert_eq!(processed(&watcher.poll()), ["README.md"]);
+}
@@ -0,0 +1,142 @@
+use fs_err as fs;
+use std::{
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::{processor::TemplateOptions, FileResult, Processor, ProjectConfig};
+
+/// Regenerates documents when any of the files they depend on changes, see
+/// [`crate::Dependencies`]. Changes are found by polling the modification time
+/// and size of the files, so it works the same on all platforms.
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use markdown_includes::{Mode, Processor, Watcher};
+///
+/// let processor = Processor::new().mode(Mode::Update);
+/// Watcher::new(processor, [("src/README.tpl.md", "README.md")])
+///     .run(Duration::from_millis(500), |file| {
+///         if let Err(e) = &file.result {
+///             eprintln!("{:?}: {e:#}", file.template);
+///         }
+///     });
+/// ```
+pub struct Watcher {
+    processor: Processor,
+    files: Vec<WatchedFile>,
+}
+
+struct WatchedFile {
+    template: PathBuf,
+    destination: PathBuf,
+    options: TemplateOptions,
+    /// The input files with their state when the file was last processed,
+    /// `None` before it has been processed
+    inputs: Option<Vec<(PathBuf, Option<FileState>)>>,
+}
+
+/// What is compared to find out whether a file changed
+#[derive(PartialEq, Eq)]
+struct FileState {
+    modified: SystemTime,
+    len: u64,
+}
+
+impl FileState {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(Self {
+            modified: metadata.modified().ok()?,
+            len: metadata.len(),
+        })
+    }
+}
+
+impl Watcher {
+    /// Watches template and destination pairs, processed by `processor`.
+    pub fn new<P1, P2, I>(processor: Processor, files: I) -> Self
+    where
+        P1: AsRef<Path>,
+        P2: AsRef<Path>,
+        I: IntoIterator<Item = (P1, P2)>,
+    {
+        let files = files
+            .into_iter()
+            .map(|(template, destination)| WatchedFile {
+                template: template.as_ref().to_path_buf(),
+                destination: destination.as_ref().to_path_buf(),
+                options: processor.options().clone(),
+                inputs: None,
+            })
+            .collect();
+        Self { processor, files }
+    }
+
+    /// Watches all files listed in a project configuration, see
+    /// [`Processor::process_project`].
+    pub fn project(processor: Processor, project: &ProjectConfig) -> Self {
+        let files = processor
+            .project_files(project)
+            .map(|(template, destination, options)| WatchedFile {
+                template,
+                destination,
+                options,
+                inputs: None,
+            })
+            .collect();
+        Self { processor, files }
+    }
+
+    /// Processes the files of which an input changed since the previous poll,
+    /// or all files on the first poll, and returns their results.
+    pub fn poll(&mut self) -> Vec<FileResult> {
+        let mut results = Vec::new();
+        for file in &mut self.files {
+            if !file.is_outdated() {
+                continue;
+            }
+            self.processor.take_dependencies();
+            let result =
+                self.processor
+                    .process_with(&file.template, &file.destination, &file.options);
+            let inputs = self
+                .processor
+                .take_dependencies()
+                .paths()
+                .map(|path| (path.to_path_buf(), FileState::of(path)))
+                .collect();
+            file.inputs = Some(inputs);
+
+            results.push(FileResult {
+                template: file.template.clone(),
+                destination: file.destination.clone(),
+                result,
+            });
+        }
+        results
+    }
+
+    /// Polls for changes every `interval`, forever, calling `on_result`
+    /// for each file that is processed.
+    pub fn run(&mut self, interval: Duration, mut on_result: impl FnMut(&FileResult)) -> ! {
+        loop {
+            for result in self.poll() {
+                on_result(&result);
+            }
+            thread::sleep(interval);
+        }
+    }
+}
+
+impl WatchedFile {
+    fn is_outdated(&self) -> bool {
+        match &self.inputs {
+            None => true,
+            Some(inputs) => inputs
+                .iter()
+                .any(|(path, state)| FileState::of(path) != *state),
+        }
+    }
+}